@@ -0,0 +1,196 @@
+use crate::providers::base::{CompletionRequest, ProviderConfig};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+/// Default size cap for the cache directory.
+const DEFAULT_MAX_BYTES: u64 = 20 * 1024 * 1024;
+
+/// A cached answer. The input text is not stored, only its hash in the
+/// file name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub text: String,
+    /// The provider or profile that produced it
+    pub provider: String,
+    pub model: String,
+}
+
+fn cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join("cache");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// `CACHE_ENABLED` (default on) and `CACHE_MAX_MB`.
+fn settings(app: &tauri::AppHandle) -> (bool, u64) {
+    let store = app.store("store.bin").ok();
+    let get = |key: &str| store.as_ref().and_then(|s| s.get(key));
+    let enabled = get("CACHE_ENABLED").and_then(|v| v.as_bool()).unwrap_or(true);
+    let max_bytes = get("CACHE_MAX_MB")
+        .and_then(|v| v.as_u64())
+        .map(|mb| mb * 1024 * 1024)
+        .unwrap_or(DEFAULT_MAX_BYTES);
+    (enabled, max_bytes)
+}
+
+/// Hashes everything that determines the answer: the provider or profile
+/// name, its resolved configuration (URL, model, headers and so on, but not
+/// the API key, so rotating a key keeps the cache), the mode, the rendered
+/// system and user messages (which contain the input text) and the sampling
+/// settings.
+pub fn cache_key(provider: &str, config: &ProviderConfig, mode: &str, request: &CompletionRequest) -> String {
+    let mut hasher = Sha256::new();
+    // Sorted so the same headers always hash the same
+    let headers: BTreeMap<&String, &String> = config.extra_headers.iter().collect();
+    let config = serde_json::to_string(&(
+        &config.provider,
+        &config.model,
+        &config.model_url,
+        config.thinking,
+        headers,
+        &config.auth_scheme,
+        &config.reasoning_dialect,
+    ))
+    .unwrap_or_default();
+    let params = serde_json::to_string(&request.params).unwrap_or_default();
+    for part in [
+        provider,
+        &config,
+        mode,
+        request.system.as_deref().unwrap_or(""),
+        &request.user,
+        &params,
+    ] {
+        // Length-prefix each part so ("ab", "c") and ("a", "bc") differ
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Returns the cached answer for `key`, marking it as recently used.
+pub fn get(app: &tauri::AppHandle, key: &str) -> Option<CachedResponse> {
+    if !settings(app).0 {
+        return None;
+    }
+    let path = cache_dir(app).ok()?.join(format!("{}.json", key));
+    let content = fs::read_to_string(&path).ok()?;
+    let cached = serde_json::from_str(&content).ok()?;
+
+    // The file's modification time is its LRU timestamp
+    if let Ok(file) = fs::File::options().write(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    Some(cached)
+}
+
+/// Stores an answer, then evicts least recently used entries until the cache
+/// is back under its size cap. Failures are logged and otherwise ignored.
+pub fn put(app: &tauri::AppHandle, key: &str, response: &CachedResponse) {
+    let (enabled, max_bytes) = settings(app);
+    if !enabled {
+        return;
+    }
+    let result = cache_dir(app).and_then(|dir| {
+        let content = serde_json::to_string(response).map_err(|e| e.to_string())?;
+        fs::write(dir.join(format!("{}.json", key)), content).map_err(|e| e.to_string())?;
+        evict(&dir, max_bytes)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to write response cache: {}", e);
+    }
+}
+
+fn evict(dir: &PathBuf, max_bytes: u64) -> Result<(), String> {
+    let mut entries: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let meta = entry.metadata().ok()?;
+            Some((meta.modified().ok()?, meta.len(), entry.path()))
+        })
+        .collect();
+
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return Ok(());
+    }
+    entries.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+    Ok(())
+}
+
+/// Deletes every cached response and returns how many were removed.
+pub fn clear(app: &tauri::AppHandle) -> Result<usize, String> {
+    let dir = cache_dir(app)?;
+    let mut removed = 0;
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn clear_response_cache(app_handle: tauri::AppHandle) -> Result<usize, String> {
+    clear(&app_handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProviderConfig {
+        ProviderConfig {
+            provider: "openai-compatible".to_string(),
+            model: Some("local-model".to_string()),
+            model_url: Some("http://localhost:8080/v1".to_string()),
+            api_key: Some("key-1".to_string()),
+            ..ProviderConfig::default()
+        }
+    }
+
+    fn key(config: &ProviderConfig) -> String {
+        cache_key("work", config, "translate", &CompletionRequest::new("Translate", "Hallo"))
+    }
+
+    #[test]
+    fn key_follows_the_configuration_but_not_the_api_key() {
+        let base = key(&config());
+
+        let rotated = ProviderConfig { api_key: Some("key-2".to_string()), ..config() };
+        assert_eq!(key(&rotated), base);
+
+        let moved = ProviderConfig { model_url: Some("http://gpu-box:8080/v1".to_string()), ..config() };
+        assert_ne!(key(&moved), base);
+
+        let mut headers = config();
+        headers.extra_headers.insert("X-Team".to_string(), "a".to_string());
+        assert_ne!(key(&headers), base);
+    }
+
+    #[test]
+    fn key_follows_the_mode() {
+        let request = CompletionRequest::new("Translate", "Hallo");
+        assert_ne!(
+            cache_key("work", &config(), "translate", &request),
+            cache_key("work", &config(), "smart", &request)
+        );
+    }
+}
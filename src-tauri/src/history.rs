@@ -1,7 +1,11 @@
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Row};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Mutex;
 use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_store::StoreExt;
 
 pub(crate) fn open_path(path: &str) {
@@ -21,32 +25,247 @@ pub struct HistoryEntry {
     pub input_text: String,
     pub source_lang: String,
     pub target_lang: String,
+    /// Detector confidence when `source_lang` was detected rather than given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_confidence: Option<f64>,
+    // Recorded since output was kept; older entries load with these unset
+    #[serde(default)]
+    pub output_text: Option<String>,
+    /// The provider or profile that answered
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    /// "default" for the built-in prompt, otherwise "custom-" and a short
+    /// hash of the template, so entries made with the same prompt group
+    #[serde(default)]
+    pub prompt_variant: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
-pub struct HistoryFile {
-    pub entries: Vec<HistoryEntry>,
+/// What a request produced, recorded alongside its input.
+pub struct HistoryOutput<'a> {
+    pub text: &'a str,
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub latency_ms: u64,
+    pub prompt_variant: String,
 }
 
-fn history_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+/// The pre-SQLite history format, read once by the importer.
+#[derive(Debug, Deserialize, Default)]
+struct HistoryFile {
+    entries: Vec<HistoryEntry>,
+}
+
+// ── Database ───────────────────────────────────────────────────────────────────
+
+/// Bumped whenever `migrate` learns a new step.
+const SCHEMA_VERSION: i64 = 3;
+
+/// Column list matching `entry_from_row`, qualified so it also works when
+/// joined with `history_fts`, whose column names overlap.
+const ENTRY_COLUMNS: &str = "history.id, history.timestamp, history.mode, history.input_text, \
+     history.source_lang, history.target_lang, history.source_confidence, history.output_text, \
+     history.provider, history.model, history.latency_ms, history.prompt_variant";
+
+/// The history database connection, held in Tauri state and opened on first
+/// use so a broken data directory only affects history, not startup.
+#[derive(Default)]
+pub struct HistoryDb(Mutex<Option<Connection>>);
+
+fn history_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    Ok(dir.join("history.json"))
+    Ok(dir)
 }
 
-pub fn load_history_file(app: &tauri::AppHandle) -> Result<HistoryFile, String> {
-    let path = history_file_path(app)?;
+fn open_db(app: &tauri::AppHandle) -> Result<Connection, String> {
+    let dir = history_dir(app)?;
+    let mut conn = Connection::open(dir.join("history.db")).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
+    migrate(&conn).map_err(|e| e.to_string())?;
+    // A file that cannot be imported is left in place for the next start
+    if let Err(e) = import_json(&dir, &mut conn) {
+        eprintln!("{}", e);
+    }
+    Ok(conn)
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version < 1 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                id                INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp         INTEGER NOT NULL,
+                mode              TEXT NOT NULL,
+                input_text        TEXT NOT NULL,
+                source_lang       TEXT NOT NULL,
+                target_lang       TEXT NOT NULL,
+                source_confidence REAL,
+                output_text       TEXT,
+                provider          TEXT,
+                model             TEXT,
+                latency_ms        INTEGER,
+                prompt_variant    TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_history_timestamp ON history(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_history_mode ON history(mode);
+            CREATE INDEX IF NOT EXISTS idx_history_langs ON history(source_lang, target_lang);",
+        )?;
+    }
+    if version < 2 {
+        // Full-text index over input and output, kept in step by triggers
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+                input_text, output_text,
+                content='history', content_rowid='id',
+                tokenize='unicode61 remove_diacritics 2'
+            );
+            CREATE TRIGGER IF NOT EXISTS history_fts_insert AFTER INSERT ON history BEGIN
+                INSERT INTO history_fts(rowid, input_text, output_text)
+                VALUES (new.id, new.input_text, new.output_text);
+            END;
+            CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history BEGIN
+                INSERT INTO history_fts(history_fts, rowid, input_text, output_text)
+                VALUES ('delete', old.id, old.input_text, old.output_text);
+            END;
+            CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE ON history BEGIN
+                INSERT INTO history_fts(history_fts, rowid, input_text, output_text)
+                VALUES ('delete', old.id, old.input_text, old.output_text);
+                INSERT INTO history_fts(rowid, input_text, output_text)
+                VALUES (new.id, new.input_text, new.output_text);
+            END;
+            INSERT INTO history_fts(history_fts) VALUES ('rebuild');",
+        )?;
+    }
+    if version < 3 {
+        // One-off facts about the database, such as the history.json import
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS meta (
+                key   TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );",
+        )?;
+    }
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+}
+
+/// `meta` key set in the same transaction as the imported entries.
+const JSON_IMPORTED_KEY: &str = "json_imported";
+
+/// Moves entries from the old `history.json` into the database in one
+/// transaction, then renames the file so it is kept as a backup. The import
+/// is recorded in `meta` along with the entries, so a failed rename cannot
+/// lead to importing them twice.
+fn import_json(dir: &std::path::Path, conn: &mut Connection) -> Result<(), String> {
+    let path = dir.join("history.json");
     if !path.exists() {
-        return Ok(HistoryFile::default());
+        return Ok(());
     }
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&content).map_err(|e| e.to_string())
+    let imported: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM meta WHERE key = ?1)", [JSON_IMPORTED_KEY], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    if !imported {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not import history.json: {}", e))?;
+        let history: HistoryFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Could not import history.json: {}", e))?;
+
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for entry in &history.entries {
+            insert_entry(&tx, entry).map_err(|e| e.to_string())?;
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+            params![JSON_IMPORTED_KEY, history.entries.len().to_string()],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    fs::rename(&path, dir.join("history.json.imported")).map_err(|e| e.to_string())
+}
+
+/// Runs `f` on the history database, opening it first if needed.
+pub(crate) fn with_db<T>(
+    app: &tauri::AppHandle,
+    f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    let state = app.state::<HistoryDb>();
+    let mut guard = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    let conn = match &mut *guard {
+        Some(conn) => conn,
+        None => guard.insert(open_db(app)?),
+    };
+    f(conn).map_err(|e| e.to_string())
+}
+
+fn insert_entry(conn: &Connection, entry: &HistoryEntry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO history (timestamp, mode, input_text, source_lang, target_lang, source_confidence,
+                              output_text, provider, model, latency_ms, prompt_variant)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            entry.timestamp as i64,
+            entry.mode,
+            entry.input_text,
+            entry.source_lang,
+            entry.target_lang,
+            entry.source_confidence,
+            entry.output_text,
+            entry.provider,
+            entry.model,
+            entry.latency_ms.map(|ms| ms as i64),
+            entry.prompt_variant,
+        ],
+    )?;
+    Ok(())
 }
 
-fn save_history_file(app: &tauri::AppHandle, history: &HistoryFile) -> Result<(), String> {
-    let path = history_file_path(app)?;
-    let content = serde_json::to_string_pretty(history).map_err(|e| e.to_string())?;
-    fs::write(path, content).map_err(|e| e.to_string())
+fn entry_from_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get::<_, i64>(0)? as u64,
+        timestamp: row.get::<_, i64>(1)? as u64,
+        mode: row.get(2)?,
+        input_text: row.get(3)?,
+        source_lang: row.get(4)?,
+        target_lang: row.get(5)?,
+        source_confidence: row.get(6)?,
+        output_text: row.get(7)?,
+        provider: row.get(8)?,
+        model: row.get(9)?,
+        latency_ms: row.get::<_, Option<i64>>(10)?.map(|ms| ms as u64),
+        prompt_variant: row.get(11)?,
+    })
+}
+
+fn query_entries(conn: &Connection, filter: &HistoryFilter) -> rusqlite::Result<Vec<HistoryEntry>> {
+    let (conditions, values) = filter.conditions();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM history {} ORDER BY history.timestamp, history.id",
+        ENTRY_COLUMNS,
+        where_clause(&conditions)
+    ))?;
+    let rows = stmt.query_map(params_from_iter(values.iter()), entry_from_row)?;
+    rows.collect()
+}
+
+/// Entries recorded at or after `since_ms` (all when `None`), oldest first.
+pub fn load_entries(app: &tauri::AppHandle, since_ms: Option<u64>) -> Result<Vec<HistoryEntry>, String> {
+    let filter = HistoryFilter {
+        since_ms,
+        ..Default::default()
+    };
+    with_db(app, |conn| query_entries(conn, &filter))
+}
+
+/// Whether any entry has been recorded, without loading them.
+pub fn has_entries(app: &tauri::AppHandle) -> Result<bool, String> {
+    with_db(app, |conn| conn.query_row("SELECT EXISTS(SELECT 1 FROM history)", [], |row| row.get(0)))
 }
 
 pub fn is_history_enabled(app: &tauri::AppHandle) -> bool {
@@ -57,34 +276,53 @@ pub fn is_history_enabled(app: &tauri::AppHandle) -> bool {
         .unwrap_or(false)
 }
 
-/// Called after every successful AI invocation to optionally record the input.
+/// Called after every successful AI invocation to optionally record the input
+/// and what it became.
 pub fn append_entry_if_enabled(
     app: &tauri::AppHandle,
     mode: &str,
     input_text: &str,
     source_lang: &str,
+    source_confidence: Option<f64>,
     target_lang: &str,
+    output: HistoryOutput,
 ) {
     if !is_history_enabled(app) || input_text.trim().is_empty() {
         return;
     }
+    if crate::retention::HistoryRetention::load(app).exclude_sensitive
+        && (crate::sensitive::contains_sensitive(input_text) || crate::sensitive::contains_sensitive(output.text))
+    {
+        return;
+    }
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_millis() as u64)
         .unwrap_or(0);
 
     let entry = HistoryEntry {
-        id: now,
+        // Assigned by the database
+        id: 0,
         timestamp: now,
         mode: mode.to_string(),
         input_text: input_text.to_string(),
         source_lang: source_lang.to_string(),
         target_lang: target_lang.to_string(),
+        source_confidence,
+        output_text: Some(output.text.to_string()),
+        provider: Some(output.provider.to_string()),
+        model: Some(output.model.to_string()),
+        latency_ms: Some(output.latency_ms),
+        prompt_variant: Some(output.prompt_variant),
     };
 
-    if let Ok(mut history) = load_history_file(app) {
-        history.entries.push(entry);
-        let _ = save_history_file(app, &history);
+    let result = with_db(app, |conn| {
+        let tx = conn.transaction()?;
+        insert_entry(&tx, &entry)?;
+        tx.commit()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record history: {}", e);
     }
 }
 
@@ -102,33 +340,255 @@ pub async fn toggle_history(app_handle: tauri::AppHandle, enabled: bool) -> Resu
     store.save().map_err(|e| e.to_string())
 }
 
+/// Number of entries for one mode and language pair.
+#[derive(Debug, Serialize)]
+pub struct HistoryCountGroup {
+    pub mode: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryCount {
+    pub total: usize,
+    /// Largest groups first
+    pub groups: Vec<HistoryCountGroup>,
+}
+
+/// Counts entries by mode and language pair, optionally only those matching
+/// `filter`.
 #[tauri::command]
-pub async fn get_history_count(app_handle: tauri::AppHandle) -> Result<usize, String> {
-    Ok(load_history_file(&app_handle)?.entries.len())
+pub async fn get_history_count(
+    app_handle: tauri::AppHandle,
+    filter: Option<HistoryFilter>,
+) -> Result<HistoryCount, String> {
+    let (conditions, values) = filter.unwrap_or_default().conditions();
+    let groups = with_db(&app_handle, |conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT mode, source_lang, target_lang, COUNT(*) FROM history {}
+             GROUP BY mode, source_lang, target_lang
+             ORDER BY COUNT(*) DESC, mode, source_lang, target_lang",
+            where_clause(&conditions)
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+            Ok(HistoryCountGroup {
+                mode: row.get(0)?,
+                source_lang: row.get(1)?,
+                target_lang: row.get(2)?,
+                count: row.get::<_, i64>(3)? as usize,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+    Ok(HistoryCount {
+        total: groups.iter().map(|g| g.count).sum(),
+        groups,
+    })
 }
 
+/// Asks where to save, then writes the matching entries there as JSON.
+/// Returns the chosen path, or `None` if the dialog was cancelled.
 #[tauri::command]
-pub async fn export_history_json(app_handle: tauri::AppHandle) -> Result<String, String> {
-    let history = load_history_file(&app_handle)?;
-    let dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
-    let export_path = dir.join("history_export.json");
+pub async fn export_history_json(
+    app_handle: tauri::AppHandle,
+    filter: Option<HistoryFilter>,
+) -> Result<Option<String>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app_handle
+        .dialog()
+        .file()
+        .add_filter("JSON", &["json"])
+        .set_file_name("history_export.json")
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let path = match rx.await.map_err(|e| e.to_string())? {
+        Some(path) => path.into_path().map_err(|e| e.to_string())?,
+        None => return Ok(None),
+    };
+
+    let filter = filter.unwrap_or_default();
+    let entries = with_db(&app_handle, |conn| query_entries(conn, &filter))?;
     let content =
-        serde_json::to_string_pretty(&history.entries).map_err(|e| e.to_string())?;
-    fs::write(&export_path, content).map_err(|e| e.to_string())?;
+        serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Deletes the entries matching `filter`, or all of them without one, and
+/// returns how many were removed. The response cache is emptied as well.
+#[tauri::command]
+pub async fn clear_history(
+    app_handle: tauri::AppHandle,
+    filter: Option<HistoryFilter>,
+) -> Result<usize, String> {
+    let (conditions, values) = filter.unwrap_or_default().conditions();
+    let removed = with_db(&app_handle, |conn| {
+        conn.execute(
+            &format!("DELETE FROM history {}", where_clause(&conditions)),
+            params_from_iter(values.iter()),
+        )
+    })?;
+    // Cached answers hold the same text but are not keyed by mode or
+    // language, so any clear empties the whole cache
+    crate::cache::clear(&app_handle)?;
+    Ok(removed)
+}
+
+// ── Search ─────────────────────────────────────────────────────────────────────
 
-    let path_str = export_path.to_string_lossy().to_string();
-    open_path(&path_str);
-    Ok(path_str)
+/// Largest page `search_history` returns.
+const MAX_PAGE_SIZE: u32 = 200;
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Narrows history by mode, language pair and time. Shared by search, count,
+/// export and clear; every field is optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryFilter {
+    pub mode: Option<String>,
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+    /// Inclusive lower bound, Unix milliseconds
+    pub since_ms: Option<u64>,
+    /// Exclusive upper bound, Unix milliseconds
+    pub until_ms: Option<u64>,
+}
+
+impl HistoryFilter {
+    /// SQL conditions on `history` and the values they bind, in order.
+    fn conditions(&self) -> (Vec<&'static str>, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        for (condition, value) in [
+            ("history.mode = ?", &self.mode),
+            ("history.source_lang = ? COLLATE NOCASE", &self.source_lang),
+            ("history.target_lang = ? COLLATE NOCASE", &self.target_lang),
+        ] {
+            if let Some(value) = value.as_ref().filter(|v| !v.is_empty()) {
+                conditions.push(condition);
+                values.push(Value::Text(value.clone()));
+            }
+        }
+        if let Some(since) = self.since_ms {
+            conditions.push("history.timestamp >= ?");
+            values.push(Value::Integer(since as i64));
+        }
+        if let Some(until) = self.until_ms {
+            conditions.push("history.timestamp < ?");
+            values.push(Value::Integer(until as i64));
+        }
+        (conditions, values)
+    }
 }
 
+fn where_clause(conditions: &[&str]) -> String {
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+}
+
+/// Arguments of `search_history`.
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    /// Words to look for in the input or output; each matches as a prefix
+    pub query: Option<String>,
+    #[serde(flatten)]
+    pub filter: HistoryFilter,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryHit {
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+    /// Excerpts around the matches, with each match wrapped in U+0002 and
+    /// U+0003 so the view can mark it without parsing HTML. `None` without
+    /// a query or when that side did not match.
+    pub input_snippet: Option<String>,
+    pub output_snippet: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistorySearchResult {
+    /// Best match first with a query, newest first without
+    pub hits: Vec<HistoryHit>,
+    /// Matches across all pages
+    pub total: u64,
+}
+
+/// Turns free text into an FTS5 query: every word quoted (so punctuation
+/// and operators are literal), prefix-matched and required.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Searches history with full-text matching and filters, one page at a time.
 #[tauri::command]
-pub async fn clear_history(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let path = history_file_path(&app_handle)?;
-    if path.exists() {
-        fs::remove_file(&path).map_err(|e| e.to_string())?;
+pub async fn search_history(
+    app_handle: tauri::AppHandle,
+    query: HistoryQuery,
+) -> Result<HistorySearchResult, String> {
+    let text = query.query.as_deref().and_then(fts_query);
+    let (mut conditions, mut values) = query.filter.conditions();
+    if let Some(text) = &text {
+        conditions.insert(0, "history_fts MATCH ?");
+        values.insert(0, Value::Text(text.clone()));
     }
-    Ok(())
+
+    let from = if text.is_some() {
+        "history JOIN history_fts ON history_fts.rowid = history.id"
+    } else {
+        "history"
+    };
+    let filter = where_clause(&conditions);
+    let (snippets, order) = if text.is_some() {
+        (
+            "snippet(history_fts, 0, char(2), char(3), '…', 16), \
+             snippet(history_fts, 1, char(2), char(3), '…', 16)",
+            // Matches in what was written outrank matches in the output
+            "bm25(history_fts, 1.0, 0.5), history.timestamp DESC",
+        )
+    } else {
+        ("NULL, NULL", "history.timestamp DESC, history.id DESC")
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0);
+
+    with_db(&app_handle, |conn| {
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} {}", from, filter),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {} FROM {} {} ORDER BY {} LIMIT {} OFFSET {}",
+            ENTRY_COLUMNS, snippets, from, filter, order, limit, offset
+        ))?;
+        let hits = stmt
+            .query_map(params_from_iter(values.iter()), |row| {
+                let snippet = |i: usize| -> rusqlite::Result<Option<String>> {
+                    // snippet() returns the plain text when nothing matched
+                    Ok(row
+                        .get::<_, Option<String>>(i)?
+                        .filter(|s| s.contains('\u{2}')))
+                };
+                Ok(HistoryHit {
+                    entry: entry_from_row(row)?,
+                    input_snippet: snippet(12)?,
+                    output_snippet: snippet(13)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(HistorySearchResult { hits, total: total as u64 })
+    })
 }
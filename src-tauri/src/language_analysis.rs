@@ -1,5 +1,6 @@
-use crate::history::{load_history_file, open_path};
-use crate::providers::base::{get_provider, Provider};
+use crate::history::{has_entries, load_entries, open_path};
+use crate::providers::base::{get_provider, CompletionRequest, Provider};
+use crate::providers::retry::{RetryPolicy, Retrying};
 use serde::Serialize;
 use std::fs;
 use std::sync::{Arc, Mutex};
@@ -16,10 +17,17 @@ fn timestamped_report_name() -> String {
         .map(|d| d.as_secs())
         .unwrap_or(0);
 
-    let days = secs / 86400;
     let rem = secs % 86400;
     let h = rem / 3600;
     let min = (rem % 3600) / 60;
+    let (year, mon, d) = utc_date(secs);
+
+    format!("analysis_{:04}-{:02}-{:02}_{:02}-{:02}.html", year, mon, d, h, min)
+}
+
+/// Converts Unix seconds to a UTC (year, month, day).
+pub(crate) fn utc_date(secs: u64) -> (i64, i64, u64) {
+    let days = secs / 86400;
 
     // Howard Hinnant civil-from-days algorithm
     let z = days as i64 + 719468;
@@ -32,8 +40,7 @@ fn timestamped_report_name() -> String {
     let d = doy - (153 * mp + 2) / 5 + 1;
     let mon: i64 = if mp < 10 { mp as i64 + 3 } else { mp as i64 - 9 };
     let year = if mon <= 2 { y + 1 } else { y };
-
-    format!("analysis_{:04}-{:02}-{:02}_{:02}-{:02}.html", year, mon, d, h, min)
+    (year, mon, d)
 }
 
 // ── Report listing ─────────────────────────────────────────────────────────────
@@ -80,14 +87,11 @@ struct AnalysisError {
 
 const ANALYSIS_PROMPT: &str = r#"You are an expert linguist and language teacher specialising in second-language acquisition (SLA).
 
-Below are writing samples from a learner collected across {ENTRY_COUNT} sessions using a translation and correction tool.
+The user message contains writing samples from a learner collected across {ENTRY_COUNT} sessions using a translation and correction tool.
 
 TARGET LANGUAGE FOR ANALYSIS: {TARGET_LANG}
 Prioritise samples written in, or corrected to, this language.
-
---- SAMPLES ---
-{FORMATTED_SAMPLES}
---- END SAMPLES ---
+Where a sample is followed by a "→ correct" or "→ refine" block, that block is the tool's output for it; compare the two to find the learner's errors.
 
 Produce a comprehensive, visually polished standalone HTML report assessing the learner's L2 proficiency. Requirements:
 - All CSS MUST be embedded inside a single <style> tag — no external stylesheets, CDN links, or JavaScript libraries
@@ -177,6 +181,18 @@ fn determine_l2_lang(
         .unwrap_or_else(|| "English".to_string())
 }
 
+/// Cuts `text` to at most `max` bytes on a character boundary.
+fn truncate(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_string();
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &text[..end])
+}
+
 fn format_samples(
     entries: &[crate::history::HistoryEntry],
     native_lang: &str,
@@ -185,19 +201,19 @@ fn format_samples(
         .iter()
         .enumerate()
         .map(|(i, e)| {
-            let text = if e.input_text.len() > 1500 {
-                format!("{}…", &e.input_text[..1500])
-            } else {
-                e.input_text.clone()
-            };
-            format!(
+            let mut sample = format!(
                 "[{}] mode={} | {} → {}\n{}",
                 i + 1,
                 e.mode,
                 e.source_lang,
                 e.target_lang,
-                text.trim()
-            )
+                truncate(&e.input_text, 1500).trim()
+            );
+            // Older entries were recorded without the model's output
+            if let Some(output) = &e.output_text {
+                sample.push_str(&format!("\n→ {}\n{}", e.mode, truncate(output, 1500).trim()));
+            }
+            sample
         })
         .collect::<Vec<_>>()
         .join("\n\n---\n\n")
@@ -313,8 +329,7 @@ pub async fn run_language_analysis(
     app_handle: tauri::AppHandle,
     days_back: Option<u32>,
 ) -> Result<(), String> {
-    let history = load_history_file(&app_handle)?;
-    if history.entries.is_empty() {
+    if !has_entries(&app_handle)? {
         return Err(
             "No history entries found. Enable history collection and use the app first."
                 .to_string(),
@@ -351,14 +366,6 @@ async fn run_analysis_inner(app: tauri::AppHandle, days_back: Option<u32>) {
         },
     );
 
-    let history = match load_history_file(&app) {
-        Ok(h) => h,
-        Err(e) => {
-            set_status(&app, AnalysisStatus { error: Some(e), ..Default::default() });
-            return;
-        }
-    };
-
     let cutoff_ms = days_back.map(|d| {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -367,10 +374,13 @@ async fn run_analysis_inner(app: tauri::AppHandle, days_back: Option<u32>) {
         now.saturating_sub(d as u64 * 86_400 * 1_000)
     });
 
-    let filtered: Vec<_> = history.entries.iter()
-        .filter(|e| cutoff_ms.map_or(true, |c| e.timestamp >= c))
-        .cloned()
-        .collect();
+    let filtered = match load_entries(&app, cutoff_ms) {
+        Ok(entries) => entries,
+        Err(e) => {
+            set_status(&app, AnalysisStatus { error: Some(e), ..Default::default() });
+            return;
+        }
+    };
     let entries = &filtered;
 
     // Resolve the user's native language from settings so we can isolate L2
@@ -403,35 +413,28 @@ async fn run_analysis_inner(app: tauri::AppHandle, days_back: Option<u32>) {
     let formatted = format_samples(entries, &native_lang);
 
     // 2 – Build prompt
-    let prompt = ANALYSIS_PROMPT
+    let instructions = ANALYSIS_PROMPT
         .replace("{ENTRY_COUNT}", &entries.len().to_string())
-        .replace("{TARGET_LANG}", &target_lang)
-        .replace("{FORMATTED_SAMPLES}", &formatted);
+        .replace("{TARGET_LANG}", &target_lang);
+    let params = app
+        .store("store.bin")
+        .map(|store| crate::commands::load_mode_params(&store, "analysis"))
+        .unwrap_or_default();
+    let request = CompletionRequest::new(
+        instructions,
+        format!("--- SAMPLES ---\n{}\n--- END SAMPLES ---", formatted),
+    )
+    .with_params(params);
 
     // 3 – Resolve provider
-    let store = match app.store("store.bin") {
-        Ok(s) => s,
+    let (provider_name, model_name) = match crate::commands::get_default_settings(&app, "analysis").await {
+        Ok(defaults) => defaults,
         Err(e) => {
-            set_status(
-                &app,
-                AnalysisStatus {
-                    error: Some(e.to_string()),
-                    ..Default::default()
-                },
-            );
+            set_status(&app, AnalysisStatus { error: Some(e), ..Default::default() });
             return;
         }
     };
 
-    let provider_name = store
-        .get("PROVIDER")
-        .and_then(|v| v.as_str().map(|s| s.to_string()))
-        .unwrap_or_else(|| "ollama".to_string());
-    let model_name = store
-        .get("MODEL")
-        .and_then(|v| v.as_str().map(|s| s.to_string()))
-        .unwrap_or_else(|| "gemma3".to_string());
-
     set_status(
         &app,
         AnalysisStatus {
@@ -447,15 +450,27 @@ async fn run_analysis_inner(app: tauri::AppHandle, days_back: Option<u32>) {
         },
     );
 
-    let provider = get_provider(app.clone(), &provider_name, &model_name);
-
-    let full_prompt = format!(
-        "<start_of_turn>user\n{}\n<end_of_turn>\n<start_of_turn>model",
-        prompt
-    );
+    let policy = app
+        .store("store.bin")
+        .map(|store| RetryPolicy::from_store(&store))
+        .unwrap_or_default();
+    let provider = match get_provider(app.clone(), &provider_name, &model_name) {
+        Ok(p) => Retrying::new(p, policy),
+        Err(e) => {
+            set_status(
+                &app,
+                AnalysisStatus {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                },
+            );
+            return;
+        }
+    };
 
-    // 4 – Call AI
-    let result = provider.completion(&full_prompt).await;
+    // Streamed so the idle timeout sees progress on a long report, and a
+    // retry never reruns a generation that was already under way
+    let result = provider.completion_stream(&request, |_| true).await;
 
     set_status(
         &app,
@@ -468,8 +483,11 @@ async fn run_analysis_inner(app: tauri::AppHandle, days_back: Option<u32>) {
     );
 
     let html = match result {
-        Ok(content) => {
-            let cleaned = crate::commands::trim_thinking_blocks(&content);
+        Ok(completion) => {
+            if let Some(usage) = completion.usage {
+                crate::usage::record(&app, &provider_name, provider.model(), usage);
+            }
+            let cleaned = crate::commands::trim_thinking_blocks(&completion.text);
             extract_html(&cleaned)
         }
         Err(e) => {
@@ -0,0 +1,332 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Below this confidence a detection is reported but not trusted for the
+/// prompt.
+pub const MIN_CONFIDENCE: f64 = 0.4;
+
+/// A language the detector can name: ISO 639-1 code, English name and the
+/// name in the language itself (the form the language pickers use).
+struct Language {
+    code: &'static str,
+    name: &'static str,
+    native: &'static str,
+}
+
+const LANGUAGES: &[Language] = &[
+    Language { code: "en", name: "English", native: "English" },
+    Language { code: "fr", name: "French", native: "Français" },
+    Language { code: "de", name: "German", native: "Deutsch" },
+    Language { code: "es", name: "Spanish", native: "Español" },
+    Language { code: "it", name: "Italian", native: "Italiano" },
+    Language { code: "pt", name: "Portuguese", native: "Português" },
+    Language { code: "nl", name: "Dutch", native: "Nederlands" },
+    Language { code: "sv", name: "Swedish", native: "Svenska" },
+    Language { code: "pl", name: "Polish", native: "Polski" },
+    Language { code: "tr", name: "Turkish", native: "Türkçe" },
+    Language { code: "vi", name: "Vietnamese", native: "Tiếng Việt" },
+    Language { code: "ru", name: "Russian", native: "Русский" },
+    Language { code: "uk", name: "Ukrainian", native: "Українська" },
+    Language { code: "el", name: "Greek", native: "Ελληνικά" },
+    Language { code: "ar", name: "Arabic", native: "العربية" },
+    Language { code: "fa", name: "Persian", native: "فارسی" },
+    Language { code: "he", name: "Hebrew", native: "עברית" },
+    Language { code: "hi", name: "Hindi", native: "हिन्दी" },
+    Language { code: "th", name: "Thai", native: "ไทย" },
+    Language { code: "ja", name: "Japanese", native: "日本語" },
+    Language { code: "zh", name: "Chinese", native: "中文" },
+    Language { code: "ko", name: "Korean", native: "한국어" },
+];
+
+/// Latin-script languages are told apart by their most frequent words and
+/// by letters few other languages use.
+struct LatinProfile {
+    code: &'static str,
+    words: &'static [&'static str],
+    marks: &'static str,
+}
+
+const LATIN_PROFILES: &[LatinProfile] = &[
+    LatinProfile {
+        code: "en",
+        words: &["the", "and", "is", "are", "of", "to", "that", "it", "you", "with", "for", "this", "was", "have", "be", "not", "on", "what", "i", "would"],
+        marks: "",
+    },
+    LatinProfile {
+        code: "fr",
+        words: &["le", "la", "les", "des", "est", "et", "une", "un", "du", "que", "qui", "dans", "pour", "pas", "vous", "je", "ce", "sur", "avec", "il"],
+        marks: "çèêëâîïôûùœ",
+    },
+    LatinProfile {
+        code: "de",
+        words: &["der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "zu", "mit", "den", "sie", "es", "auf", "für", "auch", "dem", "sich", "wir"],
+        marks: "ßäöü",
+    },
+    LatinProfile {
+        code: "es",
+        words: &["el", "la", "los", "las", "de", "que", "y", "es", "en", "un", "una", "por", "con", "para", "no", "lo", "se", "del", "está", "muy"],
+        marks: "ñ¿¡áíóú",
+    },
+    LatinProfile {
+        code: "it",
+        words: &["il", "di", "che", "è", "e", "la", "le", "un", "una", "per", "non", "sono", "con", "del", "della", "questo", "mi", "ho", "gli", "anche"],
+        marks: "àèìòù",
+    },
+    LatinProfile {
+        code: "pt",
+        words: &["o", "a", "os", "as", "de", "que", "e", "é", "em", "um", "uma", "não", "para", "com", "do", "da", "você", "por", "mais", "isso"],
+        marks: "ãõçâêô",
+    },
+    LatinProfile {
+        code: "nl",
+        words: &["de", "het", "een", "en", "van", "is", "dat", "niet", "ik", "je", "op", "met", "voor", "zijn", "er", "maar", "ook", "wat", "hij", "te"],
+        marks: "ĳ",
+    },
+    LatinProfile {
+        code: "sv",
+        words: &["och", "att", "det", "som", "en", "är", "på", "för", "med", "av", "jag", "inte", "till", "den", "har", "om", "ett", "vi", "du", "men"],
+        marks: "åäö",
+    },
+    LatinProfile {
+        code: "pl",
+        words: &["i", "w", "nie", "na", "się", "jest", "to", "z", "że", "do", "co", "jak", "ale", "tak", "o", "ja", "ten", "od", "czy", "po"],
+        marks: "ąćęłńśźż",
+    },
+    LatinProfile {
+        code: "tr",
+        words: &["ve", "bir", "bu", "da", "de", "için", "ne", "ile", "çok", "ben", "sen", "mi", "değil", "var", "olarak", "gibi", "ama", "daha", "o", "şu"],
+        marks: "ğışç",
+    },
+    LatinProfile {
+        code: "vi",
+        words: &["và", "của", "là", "có", "không", "một", "những", "được", "trong", "cho", "này", "với", "các", "người", "tôi", "bạn", "đã", "để", "khi", "thì"],
+        marks: "ơưđăạảấầẩẫậắằẳẵặẹẻẽếềểễệỉịọỏốồổỗộớờởỡợụủứừửữựỳỵỷỹĩũ",
+    },
+];
+
+/// A marker letter counts for half a frequent word.
+const MARK_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code
+    pub code: String,
+    /// English name, as used in prompts and history
+    pub name: String,
+    /// 0 to 1; short or mixed-script text scores low
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    Han,
+}
+
+fn script_of(c: char) -> Option<Script> {
+    // Multiplication and division signs sit inside the Latin-1 letters
+    if c == '×' || c == '÷' {
+        return None;
+    }
+    let script = match c as u32 {
+        0x0041..=0x005A | 0x0061..=0x007A | 0x00C0..=0x024F | 0x1E00..=0x1EFF => Script::Latin,
+        0x0370..=0x03FF | 0x1F00..=0x1FFF => Script::Greek,
+        0x0400..=0x04FF => Script::Cyrillic,
+        0x0590..=0x05FF => Script::Hebrew,
+        0x0600..=0x06FF | 0x0750..=0x077F => Script::Arabic,
+        0x0900..=0x097F => Script::Devanagari,
+        0x0E00..=0x0E7F => Script::Thai,
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+        0x3040..=0x30FF => Script::Kana,
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF => Script::Han,
+        _ => return None,
+    };
+    Some(script)
+}
+
+fn detected(code: &str, confidence: f64) -> Option<DetectedLanguage> {
+    let language = LANGUAGES.iter().find(|l| l.code == code)?;
+    Some(DetectedLanguage {
+        code: language.code.to_string(),
+        name: language.name.to_string(),
+        confidence: (confidence * 100.0).round() / 100.0,
+    })
+}
+
+/// Identifies the language of `text` offline, from its script and, for
+/// Latin-script text, from frequent words and marker letters. Returns `None`
+/// when the text has no letters or no Latin profile matches at all.
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let mut scripts: HashMap<Script, usize> = HashMap::new();
+    for script in text.chars().filter_map(script_of) {
+        *scripts.entry(script).or_default() += 1;
+    }
+    let letters: usize = scripts.values().sum();
+    if letters == 0 {
+        return None;
+    }
+    let count = |script: Script| scripts.get(&script).copied().unwrap_or(0);
+
+    // Japanese mixes kana with Han; any real amount of kana settles it
+    let kana = count(Script::Kana);
+    let cjk = kana + count(Script::Han);
+    if kana > 0 && kana * 10 >= cjk {
+        scripts.insert(Script::Kana, cjk);
+        scripts.remove(&Script::Han);
+    }
+
+    let (&script, &dominant) = scripts.iter().max_by_key(|(_, n)| **n)?;
+    let share = dominant as f64 / letters as f64;
+    match script {
+        Script::Latin => detect_latin(text).map(|(code, margin)| (code, share * margin)),
+        Script::Cyrillic => {
+            let ukrainian = text.chars().any(|c| "іїєґІЇЄҐ".contains(c));
+            Some((if ukrainian { "uk" } else { "ru" }, share))
+        }
+        Script::Arabic => {
+            let persian = text.chars().any(|c| "پچژگکی".contains(c));
+            Some((if persian { "fa" } else { "ar" }, share))
+        }
+        Script::Greek => Some(("el", share)),
+        Script::Hebrew => Some(("he", share)),
+        Script::Devanagari => Some(("hi", share)),
+        Script::Thai => Some(("th", share)),
+        Script::Hangul => Some(("ko", share)),
+        Script::Kana => Some(("ja", share)),
+        Script::Han => Some(("zh", share)),
+    }
+    .and_then(|(code, confidence)| detected(code, confidence))
+}
+
+/// Scores every Latin profile and returns the best code with a confidence
+/// that grows with the amount of evidence and its lead over the runner-up.
+fn detect_latin(text: &str) -> Option<(&'static str, f64)> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut scores: Vec<(&str, f64)> = LATIN_PROFILES
+        .iter()
+        .map(|profile| {
+            let word_hits = words.iter().filter(|w| profile.words.contains(w)).count() as f64;
+            let mark_hits = lower.chars().filter(|c| profile.marks.contains(*c)).count() as f64;
+            (profile.code, word_hits + mark_hits * MARK_WEIGHT)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let (code, best) = scores[0];
+    if best <= 0.0 {
+        return None;
+    }
+    let runner_up = scores.get(1).map(|s| s.1).unwrap_or(0.0);
+    let lead = best / (best + runner_up);
+    let evidence = best / (best + 2.0);
+    Some((code, lead * evidence))
+}
+
+/// True if `value` names the same language as `code`, whether written as a
+/// code, an English name or a native name ("vi", "Vietnamese", "Tiếng Việt").
+pub fn is_same_language(value: &str, code: &str) -> bool {
+    let value = value.trim();
+    LANGUAGES.iter().any(|l| {
+        l.code == code
+            && (l.code.eq_ignore_ascii_case(value)
+                || l.name.eq_ignore_ascii_case(value)
+                || l.native.to_lowercase() == value.to_lowercase())
+    })
+}
+
+/// True when the caller left the source language for us to work out.
+pub fn is_auto(source_lang: Option<&str>) -> bool {
+    match source_lang.map(str::trim) {
+        None => true,
+        Some(lang) => lang.is_empty() || lang.eq_ignore_ascii_case("auto"),
+    }
+}
+
+#[tauri::command]
+pub async fn detect_text_language(text: String) -> Option<DetectedLanguage> {
+    detect_language(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(text: &str) -> String {
+        detect_language(text).map(|d| d.code).unwrap_or_default()
+    }
+
+    #[test]
+    fn detects_each_script() {
+        for (text, expected) in [
+            ("Καλημέρα, πώς είσαι σήμερα;", "el"),
+            ("שלום, מה שלומך היום?", "he"),
+            ("नमस्ते, आप आज कैसे हैं?", "hi"),
+            ("สวัสดีครับ วันนี้เป็นอย่างไรบ้าง", "th"),
+            ("안녕하세요, 오늘 어떻게 지내세요?", "ko"),
+            ("你好，今天过得怎么样？", "zh"),
+            ("Привет, как у тебя дела сегодня?", "ru"),
+            ("مرحبا، كيف حالك اليوم؟", "ar"),
+        ] {
+            let detected = detect_language(text).unwrap();
+            assert_eq!(detected.code, expected, "{}", text);
+            assert!(detected.confidence >= MIN_CONFIDENCE, "{}: {}", text, detected.confidence);
+        }
+    }
+
+    #[test]
+    fn tells_ukrainian_from_russian() {
+        assert_eq!(code("Привіт, як у тебе справи сьогодні?"), "uk");
+        assert_eq!(code("Привет, как у тебя дела сегодня?"), "ru");
+    }
+
+    #[test]
+    fn tells_persian_from_arabic() {
+        assert_eq!(code("سلام، امروز چطوری؟"), "fa");
+        assert_eq!(code("مرحبا، كيف حالك اليوم؟"), "ar");
+    }
+
+    #[test]
+    fn kana_with_han_is_japanese() {
+        assert_eq!(code("今日は天気がとても良いですね。"), "ja");
+        assert_eq!(code("今天天气很好。"), "zh");
+    }
+
+    #[test]
+    fn detects_latin_languages() {
+        for (text, expected) in [
+            ("The weather is nice and I would like to go for a walk with you.", "en"),
+            ("Je pense que le temps est beau et je voudrais une promenade avec vous.", "fr"),
+            ("Ich glaube, das Wetter ist schön und wir gehen mit dem Hund spazieren.", "de"),
+            ("Creo que el tiempo está muy bien y quiero dar un paseo por la playa.", "es"),
+            ("Tôi nghĩ rằng thời tiết hôm nay rất đẹp và tôi muốn đi dạo với bạn.", "vi"),
+        ] {
+            let detected = detect_language(text).unwrap();
+            assert_eq!(detected.code, expected, "{}", text);
+            assert!(detected.confidence >= MIN_CONFIDENCE, "{}: {}", text, detected.confidence);
+        }
+    }
+
+    #[test]
+    fn short_or_ambiguous_text_is_not_trusted() {
+        for text in ["Ok", "Hello", "de la", "Taxi"] {
+            if let Some(detected) = detect_language(text) {
+                assert!(detected.confidence < MIN_CONFIDENCE, "{}: {:?}", text, detected);
+            }
+        }
+        assert!(detect_language("123 456").is_none());
+        assert!(detect_language("").is_none());
+    }
+}
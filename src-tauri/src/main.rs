@@ -1,20 +1,38 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod cache;
 mod commands;
 mod history;
 mod language_analysis;
+mod language_detection;
+mod mouse_service;
+mod profiles;
 pub mod providers;
+mod requests;
+mod retention;
 mod selected_text;
+mod sensitive;
 mod window_management;
 mod tray;
 mod shortcuts;
+mod usage;
 
-use commands::{correct, refine, translate, save_settings, get_settings, get_shortcut_window_type, open_settings_window};
-use history::{get_history_enabled, toggle_history, get_history_count, export_history_json, clear_history};
+use cache::clear_response_cache;
+use commands::{correct, refine, translate, smart_translate, save_settings, get_settings, get_shortcut_window_type, get_double_click_enabled, set_double_click_enabled, open_settings_window, validate_settings, list_models, test_provider};
+use history::{HistoryDb, get_history_enabled, toggle_history, get_history_count, export_history_json, clear_history, search_history};
+use providers::http::SharedClient;
+use requests::{cancel_request, InFlightRequests};
+use retention::{get_history_retention, set_history_retention};
+use profiles::{activate_profile, create_profile, delete_profile, list_profiles, update_profile};
+use usage::{get_price_table, get_usage_summary, set_price_table};
+use language_detection::detect_text_language;
 use language_analysis::{get_analysis_status, open_last_report, run_language_analysis, open_reports_folder, list_reports, open_report, AppAnalysisState, AnalysisStatus};
 use device_query::{DeviceQuery, DeviceState};
+use mouse_service::{MouseEvent, MouseService};
 use std::sync::{Arc, Mutex};
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
 
 use crate::tray::setup_tray;
 use crate::shortcuts::setup_shortcuts;
@@ -35,7 +53,31 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .manage(AppAnalysisState(Arc::new(Mutex::new(AnalysisStatus::default()))))
+        .manage(InFlightRequests::default())
+        .manage(HistoryDb::default())
         .setup(move |app| {
+            let store = app.store("store.bin")?;
+            providers::base::migrate_legacy_openai(&store);
+            app.manage(SharedClient::from_store(&store));
+            retention::spawn_pruning(app.handle().clone());
+
+            // Selecting text by double-click or drag opens it like the
+            // shortcut does. The service only runs while DOUBLE_CLICK_ENABLED
+            // is on; set_double_click_enabled starts and stops it
+            let handle = app.handle().clone();
+            let mouse_service = MouseService::new(move |event| {
+                if let MouseEvent::TextSelected(text) = event {
+                    let handle = handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        shortcuts::show_selected_text(&handle, &text).await;
+                    });
+                }
+            });
+            if store.get("DOUBLE_CLICK_ENABLED").and_then(|v| v.as_bool()).unwrap_or(false) {
+                mouse_service.start(app.handle().clone());
+            }
+            app.manage(mouse_service);
+
             setup_shortcuts(app)?;
             setup_tray(app).unwrap();
 
@@ -47,28 +89,60 @@ pub fn run() {
             Ok(())
         })
         .on_window_event(|window, event| {
-            if let tauri::WindowEvent::Focused(false) = event {
-                match window.label() {
-                    // Settings stays open when the user clicks elsewhere
-                    "settings" => {}
-                    _ => { let _ = window.hide(); }
+            match event {
+                tauri::WindowEvent::Focused(false) => {
+                    match window.label() {
+                        // Settings stays open when the user clicks elsewhere
+                        "settings" => {}
+                        _ => {
+                            let _ = window.hide();
+                            // Nobody is left to read the result
+                            window.state::<InFlightRequests>().cancel_window(window.label());
+                        }
+                    }
+                }
+                tauri::WindowEvent::Destroyed => {
+                    window.state::<InFlightRequests>().cancel_window(window.label());
                 }
+                _ => {}
             }
         })
         .invoke_handler(tauri::generate_handler![
             // core
-            translate, correct, refine,
+            translate, smart_translate, correct, refine,
+            cancel_request,
             get_mouse_position,
             get_shortcut_window_type,
+            get_double_click_enabled,
+            set_double_click_enabled,
             save_settings,
             get_settings,
+            validate_settings,
+            list_models,
+            test_provider,
             open_settings_window,
+            detect_text_language,
+            // provider profiles
+            list_profiles,
+            create_profile,
+            update_profile,
+            delete_profile,
+            activate_profile,
+            // token usage
+            get_usage_summary,
+            get_price_table,
+            set_price_table,
+            // response cache
+            clear_response_cache,
             // history
             get_history_enabled,
             toggle_history,
             get_history_count,
             export_history_json,
             clear_history,
+            search_history,
+            get_history_retention,
+            set_history_retention,
             // language analysis
             get_analysis_status,
             run_language_analysis,
@@ -1,6 +1,6 @@
 use device_query::{DeviceQuery, DeviceState};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -10,50 +10,27 @@ pub enum MouseEvent {
     ButtonReleased(()),
     Move(()),
     TextSelected(String),
-    ClickOutsideIcon(i32, i32), // x, y coordinates of the click
 }
 
+type Callback = Arc<dyn Fn(MouseEvent) + Send + Sync>;
+
+/// Watches the mouse for double-click and drag selections. It only polls
+/// while started, which `set_double_click_enabled` ties to the setting.
 pub struct MouseService {
-    running: Arc<AtomicBool>,
-    is_mouse_down: Arc<AtomicBool>,
-    start_pos: Arc<std::sync::Mutex<(i32, i32)>>,
-    last_click: Arc<std::sync::Mutex<Option<Instant>>>,
-    last_text_selection: Arc<std::sync::Mutex<Option<Instant>>>,
-    // Track selection icon position and visibility
-    selection_icon_bounds: Arc<std::sync::Mutex<Option<(i32, i32, i32, i32)>>>, // x, y, width, height
-    selection_icon_visible: Arc<AtomicBool>,
+    callback: Callback,
+    /// The running poller's flag; each start gets its own so a quick
+    /// stop-then-start cannot leave the old thread running
+    running: Mutex<Option<Arc<AtomicBool>>>,
 }
 
 impl MouseService {
-    pub fn new() -> Self {
+    pub fn new(callback: impl Fn(MouseEvent) + Send + Sync + 'static) -> Self {
         MouseService {
-            running: Arc::new(AtomicBool::new(false)),
-            is_mouse_down: Arc::new(AtomicBool::new(false)),
-            start_pos: Arc::new(std::sync::Mutex::new((0, 0))),
-            last_click: Arc::new(std::sync::Mutex::new(None)),
-            last_text_selection: Arc::new(std::sync::Mutex::new(None)),
-            selection_icon_bounds: Arc::new(std::sync::Mutex::new(None)),
-            selection_icon_visible: Arc::new(AtomicBool::new(false)),
+            callback: Arc::new(callback),
+            running: Mutex::new(None),
         }
     }
 
-    pub fn set_selection_icon_bounds(&self, x: i32, y: i32, width: i32, height: i32) {
-        *self.selection_icon_bounds.lock().unwrap() = Some((x, y, width, height));
-    }
-
-    pub fn set_selection_icon_visible(&self, visible: bool) {
-        self.selection_icon_visible.store(visible, Ordering::SeqCst);
-    }
-
-    fn is_click_inside_icon(&self, click_x: i32, click_y: i32) -> bool {
-        if let Some((icon_x, icon_y, width, height)) = *self.selection_icon_bounds.lock().unwrap() {
-            click_x >= icon_x && click_x <= icon_x + width &&
-            click_y >= icon_y && click_y <= icon_y + height
-        } else {
-            false
-        }
-    }
-    
     #[allow(unused_unsafe)]
     async fn check_for_selected_text(app_handle: &tauri::AppHandle, last_text: &mut String) -> Option<String> {
         if let Ok(selected_text) = unsafe { crate::selected_text::get_selected_text(app_handle).await } {
@@ -65,39 +42,35 @@ impl MouseService {
         None
     }
 
-    async fn is_double_click_enabled(app_handle: &tauri::AppHandle) -> bool {
-        match crate::commands::get_double_click_enabled(app_handle.clone()).await {
-            Ok(enabled) => enabled,
-            Err(_) => false,
+    /// Starts polling, unless it already is.
+    pub fn start(&self, app_handle: tauri::AppHandle) {
+        let mut guard = self.running.lock().unwrap();
+        if guard.is_some() {
+            return;
         }
-    }
-
-    pub fn start(&self, app_handle: tauri::AppHandle, callback: impl Fn(MouseEvent) + Send + 'static) {
-        let running = self.running.clone();
-        let is_mouse_down = self.is_mouse_down.clone();
-        let start_pos = self.start_pos.clone();
-        let last_click = self.last_click.clone();
-        let last_text_selection = self.last_text_selection.clone();
-        let selection_icon_bounds = self.selection_icon_bounds.clone();
-        let selection_icon_visible = self.selection_icon_visible.clone();
-        running.store(true, Ordering::SeqCst);
+        let running = Arc::new(AtomicBool::new(true));
+        *guard = Some(running.clone());
+        let callback = self.callback.clone();
 
         thread::spawn(move || {
             let device_state = DeviceState::new();
             let mut last_state = device_state.get_mouse();
             let mut last_text = String::new();
+            let mut is_mouse_down = false;
+            let mut start_pos = (0, 0);
+            let mut last_click: Option<Instant> = None;
+            let mut last_text_selection: Option<Instant> = None;
             const DOUBLE_CLICK_DURATION: Duration = Duration::from_millis(500);
             const TEXT_SELECTION_DEBOUNCE: Duration = Duration::from_millis(300);
             
-            // Helper function to check if we should emit text selection (debouncing)
-            let should_emit_text_selection = |last_selection: &Arc<std::sync::Mutex<Option<Instant>>>| -> bool {
-                let mut last_selection_guard = last_selection.lock().unwrap();
-                if let Some(last_time) = *last_selection_guard {
+            // Debounces selections so one gesture opens the text once
+            let should_emit_text_selection = |last_selection: &mut Option<Instant>| -> bool {
+                if let Some(last_time) = *last_selection {
                     if last_time.elapsed() < TEXT_SELECTION_DEBOUNCE {
                         return false; // Too soon, skip this selection
                     }
                 }
-                *last_selection_guard = Some(Instant::now());
+                *last_selection = Some(Instant::now());
                 true
             };
 
@@ -108,49 +81,23 @@ impl MouseService {
                 for (index, &is_pressed) in current_state.button_pressed.iter().enumerate() {
                     if is_pressed && !last_state.button_pressed[index] {
                         if index == 1 { // Left button
-                            is_mouse_down.store(true, Ordering::SeqCst);
-                            *start_pos.lock().unwrap() = current_state.coords;
-                            
+                            is_mouse_down = true;
+                            start_pos = current_state.coords;
+
                             // Check for double-click
-                            let mut last_click_guard = last_click.lock().unwrap();
-                            if let Some(last_time) = *last_click_guard {
-                                if last_time.elapsed() < DOUBLE_CLICK_DURATION {
-                                    // Check if double-click is enabled before processing
-                                    let double_click_enabled = tauri::async_runtime::block_on(async {
-                                        Self::is_double_click_enabled(&app_handle).await
-                                    });
-                                    
-                                    if double_click_enabled {
-                                        // Double click detected and enabled, check for text selection with debouncing
-                                        if should_emit_text_selection(&last_text_selection) {
-                                            if let Some(selected_text) = tauri::async_runtime::block_on(async {
-                                                Self::check_for_selected_text(&app_handle, &mut last_text).await
-                                            }) {
-                                                callback(MouseEvent::TextSelected(selected_text));
-                                            }
+                            match last_click {
+                                Some(last_time) if last_time.elapsed() < DOUBLE_CLICK_DURATION => {
+                                    // Double click detected, check for text selection with debouncing
+                                    if should_emit_text_selection(&mut last_text_selection) {
+                                        if let Some(selected_text) = tauri::async_runtime::block_on(async {
+                                            Self::check_for_selected_text(&app_handle, &mut last_text).await
+                                        }) {
+                                            callback(MouseEvent::TextSelected(selected_text));
                                         }
                                     }
-                                    *last_click_guard = None;
-                                } else {
-                                    *last_click_guard = Some(Instant::now());
-                                }
-                            } else {
-                                *last_click_guard = Some(Instant::now());
-                            }
-
-                            // Check if this is a click outside the selection icon
-                            if selection_icon_visible.load(Ordering::SeqCst) {
-                                let (click_x, click_y) = current_state.coords;
-                                let is_inside = if let Some((icon_x, icon_y, width, height)) = *selection_icon_bounds.lock().unwrap() {
-                                    click_x >= icon_x && click_x <= icon_x + width &&
-                                    click_y >= icon_y && click_y <= icon_y + height
-                                } else {
-                                    false
-                                };
-
-                                if !is_inside {
-                                    callback(MouseEvent::ClickOutsideIcon(click_x, click_y));
+                                    last_click = None;
                                 }
+                                _ => last_click = Some(Instant::now()),
                             }
                         }
                         callback(MouseEvent::ButtonPressed(()));
@@ -161,36 +108,28 @@ impl MouseService {
                 for (index, &is_pressed) in current_state.button_pressed.iter().enumerate() {
                     if !is_pressed && last_state.button_pressed[index] {
                         if index == 1 { // Left button
-                            is_mouse_down.store(false, Ordering::SeqCst);
+                            is_mouse_down = false;
                             let end_pos = current_state.coords;
-                            let start = *start_pos.lock().unwrap();
-                            
+
                             // Check for drag selection
-                            if start != end_pos {
-                                // Check if double-click is enabled before processing drag text selection
-                                let double_click_enabled = tauri::async_runtime::block_on(async {
-                                    Self::is_double_click_enabled(&app_handle).await
+                            if start_pos != end_pos {
+                                // Small delay to allow OS to update selection
+                                thread::sleep(Duration::from_millis(50));
+                                #[cfg(target_os = "windows")]
+                                let selected_text = tauri::async_runtime::block_on(async {
+                                    unsafe { crate::selected_text::get_selected_text(&app_handle).await }
                                 });
-                                
-                                if double_click_enabled {
-                                    // Small delay to allow OS to update selection
-                                    thread::sleep(Duration::from_millis(50));
-                                    #[cfg(target_os = "windows")]
-                                    let selected_text = tauri::async_runtime::block_on(async {
-                                        unsafe { crate::selected_text::get_selected_text(&app_handle).await }
-                                    });
-
-                                    #[cfg(target_os = "macos")]
-                                    let selected_text = tauri::async_runtime::block_on(async {
-                                        crate::selected_text::get_selected_text(&app_handle).await
-                                    });
-
-                                    if let Ok(selected_text) = selected_text {
-                                        if !selected_text.is_empty() && selected_text != last_text {
-                                            if should_emit_text_selection(&last_text_selection) {
-                                                last_text = selected_text.clone();
-                                                callback(MouseEvent::TextSelected(selected_text));
-                                            }
+
+                                #[cfg(target_os = "macos")]
+                                let selected_text = tauri::async_runtime::block_on(async {
+                                    crate::selected_text::get_selected_text(&app_handle).await
+                                });
+
+                                if let Ok(selected_text) = selected_text {
+                                    if !selected_text.is_empty() && selected_text != last_text {
+                                        if should_emit_text_selection(&mut last_text_selection) {
+                                            last_text = selected_text.clone();
+                                            callback(MouseEvent::TextSelected(selected_text));
                                         }
                                     }
                                 }
@@ -201,7 +140,7 @@ impl MouseService {
                 }
 
                 // Detect movement while mouse is down
-                if is_mouse_down.load(Ordering::SeqCst) && current_state.coords != last_state.coords {
+                if is_mouse_down && current_state.coords != last_state.coords {
                     callback(MouseEvent::Move(()));
                 }
 
@@ -211,7 +150,10 @@ impl MouseService {
         });
     }
 
+    /// Stops polling; the thread exits after its current 10 ms tick.
     pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
+        if let Some(running) = self.running.lock().unwrap().take() {
+            running.store(false, Ordering::SeqCst);
+        }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,211 @@
+use crate::commands::{load_fallback_chain, mode_key, DEFAULT_MODES};
+use crate::providers::base::{ProviderConfig, PROVIDER_NAMES};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::Runtime;
+use tauri_plugin_store::{Store, StoreExt};
+
+/// Profiles live in the store as `PROFILES: { name: ProviderConfig }`, with
+/// the selected one named by `ACTIVE_PROFILE`.
+const PROFILES_KEY: &str = "PROFILES";
+const ACTIVE_PROFILE_KEY: &str = "ACTIVE_PROFILE";
+
+#[derive(Serialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub config: ProviderConfig,
+    pub active: bool,
+}
+
+pub(crate) fn load_profiles<R: Runtime>(store: &Store<R>) -> BTreeMap<String, ProviderConfig> {
+    store
+        .get(PROFILES_KEY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn save_profiles<R: Runtime>(store: &Store<R>, profiles: &BTreeMap<String, ProviderConfig>) {
+    store.set(PROFILES_KEY, serde_json::json!(profiles));
+}
+
+fn active_profile<R: Runtime>(store: &Store<R>) -> Option<String> {
+    store
+        .get(ACTIVE_PROFILE_KEY)
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+}
+
+/// Mirrors a profile into the flat provider keys so `get_settings` and every
+/// caller that does not know about profiles picks it up.
+pub(crate) fn apply_to_settings<R: Runtime>(store: &Store<R>, config: &ProviderConfig) {
+    store.set("PROVIDER", config.provider.clone());
+
+    let optional = |key: &str, value: Option<&String>| match value.filter(|v| !v.is_empty()) {
+        Some(v) => store.set(key, v.clone()),
+        None => {
+            store.delete(key);
+        }
+    };
+    optional("MODEL", config.model.as_ref());
+    optional("LLM_API_KEY", config.api_key.as_ref());
+    optional("MODEL_URL", config.model_url.as_ref());
+    optional("AUTH_SCHEME", config.auth_scheme.as_ref());
+    optional("REASONING_DIALECT", config.reasoning_dialect.as_ref());
+
+    match config.thinking {
+        Some(t) => store.set("THINKING", t),
+        None => {
+            store.delete("THINKING");
+        }
+    }
+    if config.extra_headers.is_empty() {
+        store.delete("EXTRA_HEADERS");
+    } else {
+        store.set("EXTRA_HEADERS", serde_json::json!(config.extra_headers));
+    }
+}
+
+/// Settings that name `name` as a provider: mode defaults and fallback
+/// entries, e.g. `CORRECT_PROVIDER` or `FALLBACK_CHAIN`.
+fn profile_references<R: Runtime>(store: &Store<R>, name: &str) -> Vec<String> {
+    let mut references: Vec<String> = DEFAULT_MODES
+        .iter()
+        .map(|mode| mode_key(mode, "PROVIDER"))
+        .filter(|key| store.get(key).and_then(|v| v.as_str().map(|s| s == name)) == Some(true))
+        .collect();
+    if load_fallback_chain(store).iter().any(|entry| entry == name) {
+        references.push("FALLBACK_CHAIN".to_string());
+    }
+    references
+}
+
+/// The provider setup held in the flat keys, which `apply_to_settings`
+/// writes and the settings form edits directly.
+pub(crate) fn flat_settings<R: Runtime>(store: &Store<R>) -> ProviderConfig {
+    let string = |key: &str| store.get(key).and_then(|v| v.as_str().map(|s| s.to_string()));
+    let provider = string("PROVIDER").unwrap_or_else(|| "ollama".to_string());
+    ProviderConfig::from_store(store, &provider, string("MODEL").as_deref())
+}
+
+/// Unlinks the active profile after the flat keys were edited directly, so
+/// the list does not claim a profile that is no longer in use.
+pub(crate) fn clear_active_profile<R: Runtime>(store: &Store<R>) {
+    store.delete(ACTIVE_PROFILE_KEY);
+}
+
+fn check_profile(name: &str, config: &ProviderConfig) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    // get_provider looks profiles up before provider names, so a profile
+    // called "openai" would silently shadow the built-in provider
+    if PROVIDER_NAMES.contains(&name) {
+        return Err(format!("\"{}\" is a provider name and cannot be used for a profile", name));
+    }
+    let issues = config.issues();
+    if !issues.is_empty() {
+        return Err(issues.join("; "));
+    }
+    Ok(())
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+/// Lists all saved profiles, sorted by name.
+#[tauri::command]
+pub async fn list_profiles(app_handle: tauri::AppHandle) -> Result<Vec<ProfileInfo>, String> {
+    let store = app_handle.store("store.bin").map_err(|e| e.to_string())?;
+    let active = active_profile(&store);
+    Ok(load_profiles(&store)
+        .into_iter()
+        .map(|(name, config)| ProfileInfo {
+            active: active.as_deref() == Some(name.as_str()),
+            name,
+            config,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn create_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+    config: ProviderConfig,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    check_profile(&name, &config)?;
+
+    let store = app_handle.store("store.bin").map_err(|e| e.to_string())?;
+    let mut profiles = load_profiles(&store);
+    if profiles.contains_key(&name) {
+        return Err(format!("A profile named \"{}\" already exists", name));
+    }
+    profiles.insert(name, config);
+    save_profiles(&store, &profiles);
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Replaces a profile's configuration. Editing the active profile also
+/// updates the live settings.
+#[tauri::command]
+pub async fn update_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+    config: ProviderConfig,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    check_profile(&name, &config)?;
+
+    let store = app_handle.store("store.bin").map_err(|e| e.to_string())?;
+    let mut profiles = load_profiles(&store);
+    if !profiles.contains_key(&name) {
+        return Err(format!("No profile named \"{}\"", name));
+    }
+    if active_profile(&store).as_deref() == Some(name.as_str()) {
+        apply_to_settings(&store, &config);
+    }
+    profiles.insert(name, config);
+    save_profiles(&store, &profiles);
+    store.save().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_profile(app_handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    let store = app_handle.store("store.bin").map_err(|e| e.to_string())?;
+    let mut profiles = load_profiles(&store);
+    if !profiles.contains_key(&name) {
+        return Err(format!("No profile named \"{}\"", name));
+    }
+    // A dangling reference would fail every request for that mode, or be
+    // skipped as a fallback without a word
+    let references = profile_references(&store, &name);
+    if !references.is_empty() {
+        return Err(format!(
+            "Profile \"{}\" is still used by {}; change those settings first",
+            name,
+            references.join(", ")
+        ));
+    }
+    profiles.remove(&name);
+    // The flat settings keep the deleted profile's values; only the link goes
+    if active_profile(&store).as_deref() == Some(name.as_str()) {
+        store.delete(ACTIVE_PROFILE_KEY);
+    }
+    save_profiles(&store, &profiles);
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Makes a profile the default provider for every mode.
+#[tauri::command]
+pub async fn activate_profile(app_handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    let store = app_handle.store("store.bin").map_err(|e| e.to_string())?;
+    let profiles = load_profiles(&store);
+    let config = profiles
+        .get(&name)
+        .ok_or_else(|| format!("No profile named \"{}\"", name))?;
+
+    apply_to_settings(&store, config);
+    store.set(ACTIVE_PROFILE_KEY, name);
+    store.save().map_err(|e| e.to_string())
+}
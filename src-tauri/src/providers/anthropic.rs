@@ -0,0 +1,437 @@
+use crate::providers::base::{Completion, CompletionRequest, Provider, Usage};
+use crate::providers::error::ProviderError;
+use crate::providers::models::{fetch, sorted, ModelInfo};
+use crate::providers::stream::{for_each_line, sse_data};
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const MAX_TOKENS: u32 = 4096;
+// Extended thinking needs max_tokens > budget_tokens; the budget is added on top
+const THINKING_BUDGET: u32 = 2048;
+/// The Messages API takes a temperature from 0 to 1
+const MAX_TEMPERATURE: f64 = 1.0;
+
+#[derive(Serialize)]
+struct AnthropicRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<&'a str>,
+    messages: Vec<AnthropicMessage<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct AnthropicMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+struct ThinkingConfig {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    budget_tokens: u32,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicResponse {
+    content: Vec<ContentBlock>,
+    usage: Option<AnthropicUsage>,
+}
+
+/// Token counts. In a stream, `message_start` carries the input count and
+/// each `message_delta` the output count so far.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// A server-sent event from the streaming Messages API. Only text deltas,
+/// usage and errors matter here; thinking deltas and other bookkeeping
+/// events are ignored.
+#[derive(Deserialize, Debug)]
+struct AnthropicStreamEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    delta: Option<StreamDelta>,
+    error: Option<StreamError>,
+    /// Set on `message_start`
+    message: Option<StreamMessage>,
+    /// Set on `message_delta`
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamMessage {
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamDelta {
+    // message_delta's delta (stop_reason) has no type
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamError {
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicModelList {
+    data: Vec<AnthropicModel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicModel {
+    id: String,
+}
+
+pub struct AnthropicProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    base_url: String,
+    thinking: Option<bool>,
+}
+
+impl AnthropicProvider {
+    pub fn new(
+        client: Client,
+        api_key: Option<&str>,
+        model: Option<&str>,
+        base_url: Option<String>,
+        thinking: Option<bool>,
+    ) -> Self {
+        Self {
+            client,
+            api_key: api_key.unwrap_or("").to_string(),
+            model: model.unwrap_or("claude-haiku-4-5").to_string(),
+            base_url: base_url.unwrap_or_else(|| "https://api.anthropic.com/v1".to_string()),
+            thinking,
+        }
+    }
+
+    async fn send(&self, request: &CompletionRequest, stream: bool) -> Result<Response, ProviderError> {
+        let url = format!("{}/messages", self.base_url);
+
+        // Extended thinking is opt-in on the Messages API, so only THINKING=true enables it
+        let thinking = match self.thinking {
+            Some(true) => Some(ThinkingConfig {
+                kind: "enabled",
+                budget_tokens: THINKING_BUDGET,
+            }),
+            _ => None,
+        };
+        let params = &request.params;
+        let answer_tokens = params.max_tokens.unwrap_or(MAX_TOKENS);
+        let max_tokens = if thinking.is_some() {
+            answer_tokens.saturating_add(THINKING_BUDGET)
+        } else {
+            answer_tokens
+        };
+        // Extended thinking rejects sampling changes, and the API has no seed.
+        // Mode params allow up to 2.0 for other providers, but Anthropic
+        // rejects a temperature above 1.0
+        let (temperature, top_p) = if thinking.is_some() {
+            (None, None)
+        } else {
+            (params.temperature.map(|t| t.min(MAX_TEMPERATURE)), params.top_p)
+        };
+
+        let body = AnthropicRequest {
+            model: &self.model,
+            max_tokens,
+            system: request.system.as_deref(),
+            messages: vec![AnthropicMessage {
+                role: "user",
+                content: &request.user,
+            }],
+            thinking,
+            temperature,
+            top_p,
+            stream,
+        };
+
+        let res = self
+            .client
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| ProviderError::from_reqwest("Anthropic", &self.base_url, &err))?;
+
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(ProviderError::from_response("Anthropic", res).await)
+        }
+    }
+}
+
+impl Provider for AnthropicProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        let res = self.send(request, false).await?;
+        let data = res
+            .json::<AnthropicResponse>()
+            .await
+            .map_err(|err| ProviderError::from_reqwest("Anthropic", &self.base_url, &err))?;
+
+        // Thinking blocks come first when enabled; keep only the answer text
+        let text: String = data
+            .content
+            .iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text.as_str())
+            .collect();
+        if text.is_empty() {
+            Err(ProviderError::bad_response("No content in Anthropic response"))
+        } else {
+            Ok(Completion {
+                text,
+                usage: data.usage.map(|u| Usage {
+                    prompt_tokens: u.input_tokens,
+                    completion_tokens: u.output_tokens,
+                }),
+            })
+        }
+    }
+
+    async fn completion_stream<F>(&self, request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        let res = self.send(request, true).await?;
+        let mut output = String::new();
+        let mut usage: Option<Usage> = None;
+
+        for_each_line("Anthropic", res, |line| {
+            let Some(data) = sse_data(line) else {
+                return Ok(true);
+            };
+            let event: AnthropicStreamEvent = serde_json::from_str(data).map_err(|e| {
+                ProviderError::bad_response(format!("Failed to deserialize Anthropic response: {}", e))
+            })?;
+            match event.kind.as_str() {
+                "content_block_delta" => {
+                    if let Some(delta) = event.delta.filter(|d| d.kind == "text_delta") {
+                        if !delta.text.is_empty() {
+                            output.push_str(&delta.text);
+                            return Ok(on_chunk(&delta.text));
+                        }
+                    }
+                    Ok(true)
+                }
+                "message_start" => {
+                    if let Some(u) = event.message.and_then(|m| m.usage) {
+                        usage = Some(Usage {
+                            prompt_tokens: u.input_tokens,
+                            completion_tokens: u.output_tokens,
+                        });
+                    }
+                    Ok(true)
+                }
+                "message_delta" => {
+                    if let Some(u) = event.usage {
+                        usage.get_or_insert_with(Usage::default).completion_tokens = u.output_tokens;
+                    }
+                    Ok(true)
+                }
+                "message_stop" => Ok(false),
+                "error" => Err(ProviderError::Other {
+                    message: format!(
+                        "Anthropic error: {}",
+                        event.error.map(|e| e.message).unwrap_or_default()
+                    ),
+                }),
+                _ => Ok(true),
+            }
+        })
+        .await?;
+
+        Ok(Completion { text: output, usage })
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let request = self
+            .client
+            .get(format!("{}/models?limit=1000", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION);
+        let list: AnthropicModelList = fetch("Anthropic", &self.base_url, request).await?;
+        Ok(sorted(
+            list.data
+                .into_iter()
+                .map(|m| ModelInfo {
+                    id: m.id,
+                    context_length: None,
+                    supports_thinking: None,
+                })
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::GenerationParams;
+    use crate::providers::test_server::{MockResponse, MockServer};
+    use serde_json::json;
+
+    fn provider(server: &MockServer, thinking: Option<bool>) -> AnthropicProvider {
+        AnthropicProvider::new(
+            Client::new(),
+            Some("test-key"),
+            Some("claude-test"),
+            Some(format!("{}/custom/v1", server.url)),
+            thinking,
+        )
+    }
+
+    fn answer(content: serde_json::Value) -> MockResponse {
+        MockResponse::json(
+            200,
+            json!({ "content": content, "usage": { "input_tokens": 12, "output_tokens": 3 } }),
+        )
+    }
+
+    #[tokio::test]
+    async fn sends_auth_headers_to_the_configured_url() {
+        let server = MockServer::start(vec![answer(json!([{ "type": "text", "text": "Hi" }]))]).await;
+        let request = CompletionRequest::new("Translate", "Hallo");
+        provider(&server, None).completion(&request).await.unwrap();
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.method, "POST");
+        assert_eq!(sent.path, "/custom/v1/messages");
+        assert_eq!(sent.header("x-api-key"), Some("test-key"));
+        assert_eq!(sent.header("anthropic-version"), Some(ANTHROPIC_VERSION));
+
+        let body = sent.json();
+        assert_eq!(body["model"], "claude-test");
+        assert_eq!(body["system"], "Translate");
+        assert_eq!(body["messages"][0]["content"], "Hallo");
+        assert_eq!(body["max_tokens"], MAX_TOKENS);
+        assert!(body.get("thinking").is_none());
+    }
+
+    #[tokio::test]
+    async fn thinking_adds_the_budget_on_top_of_max_tokens() {
+        let server = MockServer::start(vec![answer(json!([{ "type": "text", "text": "Hi" }]))]).await;
+        let request = CompletionRequest::new("Translate", "Hallo").with_params(GenerationParams {
+            temperature: Some(0.2),
+            max_tokens: Some(1000),
+            ..Default::default()
+        });
+        provider(&server, Some(true)).completion(&request).await.unwrap();
+
+        let body = server.requests()[0].json();
+        assert_eq!(body["thinking"]["type"], "enabled");
+        assert_eq!(body["thinking"]["budget_tokens"], THINKING_BUDGET);
+        assert_eq!(body["max_tokens"], 1000 + THINKING_BUDGET);
+        assert!(body.get("temperature").is_none());
+    }
+
+    #[tokio::test]
+    async fn clamps_temperature_to_the_api_limit() {
+        let server = MockServer::start(vec![answer(json!([{ "type": "text", "text": "Hi" }]))]).await;
+        let request = CompletionRequest::new("Translate", "Hallo").with_params(GenerationParams {
+            temperature: Some(1.6),
+            ..Default::default()
+        });
+        provider(&server, None).completion(&request).await.unwrap();
+
+        assert_eq!(server.requests()[0].json()["temperature"], MAX_TEMPERATURE);
+    }
+
+    #[tokio::test]
+    async fn keeps_only_text_blocks() {
+        let server = MockServer::start(vec![answer(json!([
+            { "type": "thinking", "thinking": "The user wants German." },
+            { "type": "text", "text": "Hello" },
+            { "type": "text", "text": " world" },
+        ]))])
+        .await;
+        let completion = provider(&server, Some(true))
+            .completion(&CompletionRequest::user("Hallo Welt"))
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "Hello world");
+        let usage = completion.usage.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (12, 3));
+    }
+
+    #[tokio::test]
+    async fn parses_stream_events() {
+        let server = MockServer::start(vec![MockResponse::sse(&[
+            json!({ "type": "message_start", "message": { "usage": { "input_tokens": 20, "output_tokens": 1 } } }),
+            json!({ "type": "content_block_delta", "delta": { "type": "thinking_delta", "thinking": "Hmm" } }),
+            json!({ "type": "content_block_delta", "delta": { "type": "text_delta", "text": "Hel" } }),
+            json!({ "type": "ping" }),
+            json!({ "type": "content_block_delta", "delta": { "type": "text_delta", "text": "lo" } }),
+            json!({ "type": "message_delta", "delta": { "stop_reason": "end_turn" }, "usage": { "output_tokens": 7 } }),
+            json!({ "type": "message_stop" }),
+        ])])
+        .await;
+
+        let mut chunks = Vec::new();
+        let completion = provider(&server, None)
+            .completion_stream(&CompletionRequest::user("Hallo"), |delta| {
+                chunks.push(delta.to_string());
+                true
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(chunks, ["Hel", "lo"]);
+        assert_eq!(completion.text, "Hello");
+        let usage = completion.usage.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (20, 7));
+        assert_eq!(server.requests()[0].json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn stream_error_event_fails_the_request() {
+        let server = MockServer::start(vec![MockResponse::sse(&[
+            json!({ "type": "content_block_delta", "delta": { "type": "text_delta", "text": "Hel" } }),
+            json!({ "type": "error", "error": { "type": "overloaded_error", "message": "Overloaded" } }),
+        ])])
+        .await;
+
+        let err = provider(&server, None)
+            .completion_stream(&CompletionRequest::user("Hallo"), |_| true)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(&err, ProviderError::Other { message } if message.contains("Overloaded")));
+    }
+}
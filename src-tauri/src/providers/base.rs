@@ -1,11 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::future::Future;
-use tauri::AppHandle;
-use tauri_plugin_store::StoreBuilder;
+use reqwest::Client;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::{Store, StoreBuilder};
 
-use super::{gemini::GeminiProvider, groq::GroqProvider, ollama::OllamaProvider, openai::OpenAIProvider};
+use super::error::ProviderError;
+use super::http::SharedClient;
+use super::models::ModelInfo;
+use super::openai_compatible::{parse_extra_headers, AuthScheme, OpenAICompatibleProvider, ReasoningDialect};
+use super::{anthropic::AnthropicProvider, gemini::GeminiProvider, groq::GroqProvider, ollama::OllamaProvider, openai::OpenAIProvider};
+
+/// Tokens consumed by one request, as reported by the provider. The field
+/// names match OpenAI's `usage` object, so OpenAI-style responses
+/// deserialize into it directly.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// A finished completion.
+#[derive(Debug, Clone, Default)]
+pub struct Completion {
+    pub text: String,
+    /// `None` when the provider did not report usage, or the stream was
+    /// stopped before it did
+    pub usage: Option<Usage>,
+}
+
+/// Sampling settings for a request. Unset fields are left out so the
+/// provider's own default applies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationParams {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    /// Cap on generated tokens, not counting Anthropic's thinking budget
+    pub max_tokens: Option<u32>,
+    /// Fixed seed for repeatable output where the provider supports it;
+    /// Anthropic does not
+    pub seed: Option<u64>,
+}
+
+impl GenerationParams {
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        if self.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+            issues.push("Temperature must be between 0 and 2".to_string());
+        }
+        if self.top_p.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+            issues.push("Top P must be between 0 and 1".to_string());
+        }
+        if self.max_tokens == Some(0) {
+            issues.push("Max tokens must be at least 1".to_string());
+        }
+        issues
+    }
+
+    /// Top-level fields for OpenAI-style chat APIs. `max_tokens_field` is
+    /// `max_completion_tokens` for OpenAI and Groq, `max_tokens` for servers
+    /// that have not adopted the newer name.
+    pub(crate) fn openai_fields(&self, max_tokens_field: &str) -> serde_json::Map<String, serde_json::Value> {
+        let mut fields = serde_json::Map::new();
+        if let Some(t) = self.temperature {
+            fields.insert("temperature".into(), t.into());
+        }
+        if let Some(p) = self.top_p {
+            fields.insert("top_p".into(), p.into());
+        }
+        if let Some(n) = self.max_tokens {
+            fields.insert(max_tokens_field.into(), n.into());
+        }
+        if let Some(seed) = self.seed {
+            fields.insert("seed".into(), seed.into());
+        }
+        fields
+    }
+}
+
+/// A prompt as separate messages: the instruction goes in `system` and the
+/// text it applies to in `user`, so each provider can send them in its own
+/// native format instead of one concatenated string.
+#[derive(Debug, Clone, Default)]
+pub struct CompletionRequest {
+    pub system: Option<String>,
+    pub user: String,
+    pub params: GenerationParams,
+}
+
+impl CompletionRequest {
+    pub fn new(system: impl Into<String>, user: impl Into<String>) -> Self {
+        Self {
+            system: Some(system.into()),
+            user: user.into(),
+            params: GenerationParams::default(),
+        }
+    }
+
+    /// A request with no system message.
+    pub fn user(user: impl Into<String>) -> Self {
+        Self {
+            system: None,
+            user: user.into(),
+            params: GenerationParams::default(),
+        }
+    }
+
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// The `messages` array used by chat APIs (OpenAI, Groq, Ollama's
+    /// `/api/chat`).
+    pub(crate) fn chat_messages(&self) -> serde_json::Value {
+        let mut messages = Vec::new();
+        if let Some(system) = &self.system {
+            messages.push(serde_json::json!({ "role": "system", "content": system }));
+        }
+        messages.push(serde_json::json!({ "role": "user", "content": self.user }));
+        serde_json::Value::Array(messages)
+    }
+}
 
 pub trait Provider {
-    fn completion(&self, prompt: &str) -> impl Future<Output = Result<String, String>>;
+    /// The model this provider sends requests to.
+    fn model(&self) -> &str;
+
+    fn completion(&self, request: &CompletionRequest) -> impl Future<Output = Result<Completion, ProviderError>>;
+
+    /// Streams the completion, calling `on_chunk` with each text fragment as it
+    /// arrives. Returning `false` from `on_chunk` stops the stream early.
+    /// Resolves to the full output received so far.
+    fn completion_stream<F>(
+        &self,
+        request: &CompletionRequest,
+        on_chunk: F,
+    ) -> impl Future<Output = Result<Completion, ProviderError>>
+    where
+        F: FnMut(&str) -> bool + Send;
+
+    /// Lists the models the server or account offers.
+    fn list_models(&self) -> impl Future<Output = Result<Vec<ModelInfo>, ProviderError>>;
 }
 
 pub enum ProviderEnum {
@@ -13,65 +149,304 @@ pub enum ProviderEnum {
     OpenAIProvider(OpenAIProvider),
     GeminiProvider(GeminiProvider),
     GroqProvider(GroqProvider),
+    AnthropicProvider(AnthropicProvider),
+    OpenAICompatibleProvider(OpenAICompatibleProvider),
 }
 
 impl Provider for ProviderEnum {
-    async fn completion(&self, prompt: &str) -> Result<String, String> {
+    fn model(&self) -> &str {
+        match self {
+            ProviderEnum::OllamaProvider(provider) => provider.model(),
+            ProviderEnum::OpenAIProvider(provider) => provider.model(),
+            ProviderEnum::GeminiProvider(provider) => provider.model(),
+            ProviderEnum::GroqProvider(provider) => provider.model(),
+            ProviderEnum::AnthropicProvider(provider) => provider.model(),
+            ProviderEnum::OpenAICompatibleProvider(provider) => provider.model(),
+        }
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        match self {
+            ProviderEnum::OllamaProvider(provider) => provider.completion(request).await,
+            ProviderEnum::OpenAIProvider(provider) => provider.completion(request).await,
+            ProviderEnum::GeminiProvider(provider) => provider.completion(request).await,
+            ProviderEnum::GroqProvider(provider) => provider.completion(request).await,
+            ProviderEnum::AnthropicProvider(provider) => provider.completion(request).await,
+            ProviderEnum::OpenAICompatibleProvider(provider) => provider.completion(request).await,
+        }
+    }
+
+    async fn completion_stream<F>(&self, request: &CompletionRequest, on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        match self {
+            ProviderEnum::OllamaProvider(provider) => provider.completion_stream(request, on_chunk).await,
+            ProviderEnum::OpenAIProvider(provider) => provider.completion_stream(request, on_chunk).await,
+            ProviderEnum::GeminiProvider(provider) => provider.completion_stream(request, on_chunk).await,
+            ProviderEnum::GroqProvider(provider) => provider.completion_stream(request, on_chunk).await,
+            ProviderEnum::AnthropicProvider(provider) => provider.completion_stream(request, on_chunk).await,
+            ProviderEnum::OpenAICompatibleProvider(provider) => provider.completion_stream(request, on_chunk).await,
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
         match self {
-            ProviderEnum::OllamaProvider(provider) => provider.completion(prompt).await,
-            ProviderEnum::OpenAIProvider(provider) => provider.completion(prompt).await,
-            ProviderEnum::GeminiProvider(provider) => provider.completion(prompt).await,
-            ProviderEnum::GroqProvider(provider) => provider.completion(prompt).await,
+            ProviderEnum::OllamaProvider(provider) => provider.list_models().await,
+            ProviderEnum::OpenAIProvider(provider) => provider.list_models().await,
+            ProviderEnum::GeminiProvider(provider) => provider.list_models().await,
+            ProviderEnum::GroqProvider(provider) => provider.list_models().await,
+            ProviderEnum::AnthropicProvider(provider) => provider.list_models().await,
+            ProviderEnum::OpenAICompatibleProvider(provider) => provider.list_models().await,
+        }
+    }
+}
+
+/// Provider names accepted by `get_provider`.
+pub const PROVIDER_NAMES: &[&str] = &["ollama", "openai", "gemini", "groq", "anthropic", "openai-compatible"];
+
+/// Everything needed to build a provider.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub provider: String,
+    pub model: Option<String>,
+    pub model_url: Option<String>,
+    pub api_key: Option<String>,
+    pub thinking: Option<bool>,
+    /// openai-compatible only: headers added to every request
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+    /// openai-compatible only: `bearer` (default), `none` or `header:<Name>`
+    pub auth_scheme: Option<String>,
+    /// openai-compatible only: `chat_template_kwargs` (default), `reasoning_effort` or `none`
+    pub reasoning_dialect: Option<String>,
+}
+
+impl ProviderConfig {
+    /// Reads the single provider configuration saved by `save_settings`. The
+    /// saved URL, key and connection options belong to the `PROVIDER` they
+    /// were entered for; any other provider gets its defaults, so a per-mode
+    /// or fallback "groq" is never sent to the URL saved for Ollama.
+    pub fn from_store<R: Runtime>(store: &Store<R>, provider: &str, model: Option<&str>) -> Self {
+        let string = |key: &str| store.get(key).and_then(|v| v.as_str().map(|s| s.to_string()));
+        let thinking = store
+            .get("THINKING")
+            .or_else(|| store.get("OLLAMA_THINKING"))
+            .and_then(|v| v.as_bool());
+
+        let saved_provider = string("PROVIDER").unwrap_or_else(|| "ollama".to_string());
+        if provider != saved_provider {
+            return Self {
+                provider: provider.to_string(),
+                model: model.map(|m| m.to_string()),
+                thinking,
+                ..Self::default()
+            };
+        }
+
+        Self {
+            provider: provider.to_string(),
+            model: model.map(|m| m.to_string()),
+            model_url: string("MODEL_URL").or_else(|| string("OLLAMA_ENDPOINT")),
+            api_key: string("LLM_API_KEY"),
+            thinking,
+            extra_headers: store
+                .get("EXTRA_HEADERS")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+            auth_scheme: string("AUTH_SCHEME"),
+            reasoning_dialect: string("REASONING_DIALECT"),
+        }
+    }
+
+    /// Returns every problem with this configuration, or an empty list if it
+    /// is usable. Ollama runs locally and never needs a key; an
+    /// openai-compatible server needs one only when it uses auth.
+    pub fn issues(&self) -> Vec<String> {
+        self.check(true)
+    }
+
+    fn check(&self, require_model: bool) -> Vec<String> {
+        let mut issues = Vec::new();
+        let provider = self.provider.as_str();
+        let has_key = !self.api_key.as_deref().unwrap_or("").trim().is_empty();
+
+        match provider {
+            "ollama" => {}
+            "openai-compatible" => {
+                if self.model_url.as_deref().unwrap_or("").is_empty() {
+                    issues.push("A server URL is required for an OpenAI-compatible provider".to_string());
+                }
+                if require_model && self.model.as_deref().unwrap_or("").is_empty() {
+                    issues.push("A model name is required for an OpenAI-compatible provider".to_string());
+                }
+                match AuthScheme::parse(self.auth_scheme.as_deref()) {
+                    Ok(AuthScheme::None) => {}
+                    Ok(_) if !has_key => {
+                        issues.push("An API key is required unless the auth scheme is \"none\"".to_string())
+                    }
+                    Ok(_) => {}
+                    Err(e) => issues.push(e),
+                }
+                if let Err(e) = ReasoningDialect::parse(self.reasoning_dialect.as_deref()) {
+                    issues.push(e);
+                }
+                if let Err(e) = parse_extra_headers(&self.extra_headers) {
+                    issues.push(e);
+                }
+            }
+            _ if !PROVIDER_NAMES.contains(&provider) => issues.push(format!(
+                "Unknown provider \"{}\" (expected one of: {})",
+                provider,
+                PROVIDER_NAMES.join(", ")
+            )),
+            _ if !has_key => issues.push(format!("An API key is required for {}", provider)),
+            _ => {}
+        }
+
+        if let Some(url) = self.model_url.as_deref().filter(|u| !u.is_empty()) {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {}
+                Ok(parsed) => issues.push(format!(
+                    "Model URL must use http or https, not \"{}\"",
+                    parsed.scheme()
+                )),
+                Err(e) => issues.push(format!("Model URL \"{}\" is not valid: {}", url, e)),
+            }
+        }
+
+        issues
+    }
+
+    /// Builds the provider on `client`, failing with a configuration error
+    /// instead of sending a request that cannot succeed.
+    pub fn build(&self, client: Client) -> Result<ProviderEnum, ProviderError> {
+        self.build_checked(client, true)
+    }
+
+    /// Like `build`, but without requiring a model, so the provider's models
+    /// can be listed before one is chosen.
+    pub fn build_for_listing(&self, client: Client) -> Result<ProviderEnum, ProviderError> {
+        self.build_checked(client, false)
+    }
+
+    fn build_checked(&self, client: Client, require_model: bool) -> Result<ProviderEnum, ProviderError> {
+        if let Some(issue) = self.check(require_model).into_iter().next() {
+            return Err(ProviderError::config(issue));
+        }
+
+        let api_key = self.api_key.as_deref();
+        let model = self.model.as_deref();
+        let model_url = self.model_url.clone();
+        let thinking = self.thinking;
+
+        let provider_enum = match self.provider.as_str() {
+            "ollama" => ProviderEnum::OllamaProvider(OllamaProvider::new(client, model_url, self.model.clone(), thinking)),
+            "openai" => ProviderEnum::OpenAIProvider(OpenAIProvider::new(client, api_key, model, model_url, thinking)),
+            "gemini" => ProviderEnum::GeminiProvider(GeminiProvider::new(client, api_key, model, model_url, thinking)),
+            "groq" => ProviderEnum::GroqProvider(GroqProvider::new(client, api_key, model, model_url, thinking)),
+            "anthropic" => {
+                ProviderEnum::AnthropicProvider(AnthropicProvider::new(client, api_key, model, model_url, thinking))
+            }
+            "openai-compatible" => ProviderEnum::OpenAICompatibleProvider(OpenAICompatibleProvider::new(
+                client,
+                api_key,
+                model.unwrap_or_default(),
+                model_url.unwrap_or_default(),
+                thinking,
+                parse_extra_headers(&self.extra_headers).map_err(ProviderError::config)?,
+                AuthScheme::parse(self.auth_scheme.as_deref()).map_err(ProviderError::config)?,
+                ReasoningDialect::parse(self.reasoning_dialect.as_deref()).map_err(ProviderError::config)?,
+            )),
+            other => return Err(ProviderError::config(format!("Unknown provider \"{}\"", other))),
+        };
+        Ok(provider_enum)
+    }
+}
+
+/// Resolves `name` to a configuration: a saved profile of that name wins,
+/// otherwise it is taken as a provider name using the flat settings. A
+/// non-empty `model` overrides the profile's own model.
+pub fn resolve_config<R: Runtime>(store: &Store<R>, name: &str, model: Option<&str>) -> ProviderConfig {
+    resolve_config_with(store, &crate::profiles::flat_settings(store), name, model)
+}
+
+/// Like `resolve_config`, with `flat` standing in for the flat settings.
+/// `save_settings` passes the values it is about to write so it can check
+/// them before anything is saved.
+pub fn resolve_config_with<R: Runtime>(
+    store: &Store<R>,
+    flat: &ProviderConfig,
+    name: &str,
+    model: Option<&str>,
+) -> ProviderConfig {
+    let model = model.filter(|m| !m.is_empty()).map(|m| m.to_string());
+    match crate::profiles::load_profiles(store).remove(name) {
+        Some(mut profile) => {
+            if model.is_some() {
+                profile.model = model;
+            }
+            profile
         }
+        None if name == flat.provider => ProviderConfig { model, ..flat.clone() },
+        // Only PROVIDER has a saved URL and key; THINKING applies to all
+        None => ProviderConfig {
+            provider: name.to_string(),
+            model,
+            thinking: flat.thinking,
+            ..ProviderConfig::default()
+        },
     }
 }
 
-pub fn get_provider(app_handler: AppHandle, provider: &str, model: &str) -> ProviderEnum {
+/// Set once `migrate_legacy_openai` has run, so it never runs again.
+const OPENAI_MIGRATED_KEY: &str = "OPENAI_COMPATIBLE_MIGRATED";
+
+/// Before the openai-compatible provider existed, llama.cpp and vLLM servers
+/// were saved as PROVIDER "openai" with their own MODEL_URL. Moves such a
+/// setup, and the mode defaults and fallbacks naming it, to
+/// "openai-compatible" so it keeps the `chat_template_kwargs` switch it was
+/// set up for. Runs once per store; an "openai" setup saved afterwards with
+/// a custom URL (an Azure or proxy endpoint) is left alone.
+pub fn migrate_legacy_openai<R: Runtime>(store: &Store<R>) {
+    if store.get(OPENAI_MIGRATED_KEY).is_some() {
+        return;
+    }
+    let string = |key: &str| store.get(key).and_then(|v| v.as_str().map(|s| s.to_string()));
+    let self_hosted = string("MODEL_URL")
+        .and_then(|url| reqwest::Url::parse(&url).ok())
+        .is_some_and(|url| url.host_str() != Some("api.openai.com"));
+
+    if string("PROVIDER").as_deref() == Some("openai") && self_hosted {
+        store.set("PROVIDER", "openai-compatible");
+        for key in store.keys().into_iter().filter(|k| k.ends_with("_PROVIDER")) {
+            if string(&key).as_deref() == Some("openai") {
+                store.set(key, "openai-compatible");
+            }
+        }
+        if let Some(chain) = store
+            .get("FALLBACK_CHAIN")
+            .and_then(|v| serde_json::from_value::<Vec<String>>(v).ok())
+        {
+            let chain: Vec<String> = chain
+                .into_iter()
+                .map(|name| if name == "openai" { "openai-compatible".to_string() } else { name })
+                .collect();
+            store.set("FALLBACK_CHAIN", serde_json::json!(chain));
+        }
+    }
+    store.set(OPENAI_MIGRATED_KEY, true);
+    if let Err(e) = store.save() {
+        eprintln!("Failed to save migrated provider settings: {}", e);
+    }
+}
+
+/// Builds the provider for a profile or provider name.
+pub fn get_provider(app_handler: AppHandle, provider: &str, model: &str) -> Result<ProviderEnum, ProviderError> {
     let store = StoreBuilder::new(&app_handler, "store.bin")
         .build()
-        .expect("Failed to build store");
-
-    let model_url = store
-        .get("MODEL_URL")
-        .or_else(|| store.get("OLLAMA_ENDPOINT"))
-        .and_then(|v| v.as_str().map(|s| s.to_string()));
-
-    let thinking = store
-        .get("THINKING")
-        .or_else(|| store.get("OLLAMA_THINKING"))
-        .and_then(|v| v.as_bool());
-
-    match provider {
-        "ollama" => {
-            ProviderEnum::OllamaProvider(OllamaProvider::new(model_url, Some(model.to_string()), thinking))
-        }
-        "openai" => {
-            let api_key_value = store
-                .get("LLM_API_KEY")
-                .expect("Failed to get API key from store");
-            let api_key = api_key_value
-                .as_str()
-                .expect("API key is not a valid string");
-            ProviderEnum::OpenAIProvider(OpenAIProvider::new(Some(api_key), Some(model), model_url, thinking))
-        }
-        "gemini" => {
-            let api_key_value = store
-                .get("LLM_API_KEY")
-                .expect("Failed to get API key from store");
-            let api_key = api_key_value
-                .as_str()
-                .expect("API key is not a valid string");
-            ProviderEnum::GeminiProvider(GeminiProvider::new(Some(api_key), Some(model), model_url, thinking))
-        }
-        "groq" => {
-            let api_key_value = store
-                .get("LLM_API_KEY")
-                .expect("Failed to get API key from store");
-            let api_key = api_key_value
-                .as_str()
-                .expect("API key is not a valid string");
-            ProviderEnum::GroqProvider(GroqProvider::new(Some(api_key), Some(model), model_url, thinking))
-        }
-        _ => panic!("Invalid provider"),
-    }
+        .map_err(|e| ProviderError::config(format!("Failed to open settings store: {}", e)))?;
+
+    let client = app_handler.state::<SharedClient>().get();
+    resolve_config(&store, provider, Some(model)).build(client)
 }
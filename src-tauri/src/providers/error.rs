@@ -0,0 +1,181 @@
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use serde::Serialize;
+use std::error::Error as StdError;
+use std::fmt;
+use std::time::Duration;
+
+/// Failure of a provider call, serialised to the frontend as
+/// `{ "kind": "rate_limited", "message": "..." }` so it can react per kind
+/// instead of pattern-matching on message text.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProviderError {
+    ConnectionRefused { message: String },
+    AuthFailed { message: String },
+    RateLimited {
+        message: String,
+        /// Seconds the server asked us to wait, from its `Retry-After` header
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retry_after: Option<u64>,
+    },
+    ModelNotFound { message: String },
+    BadResponse { message: String },
+    Timeout { message: String },
+    /// Any other non-success HTTP status
+    Http {
+        status: u16,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retry_after: Option<u64>,
+    },
+    /// The provider settings are missing or invalid; nothing was sent
+    Config { message: String },
+    Cancelled,
+    Other { message: String },
+}
+
+impl ProviderError {
+    /// Classifies a non-success HTTP response. `provider` is the display name
+    /// used in the message, e.g. "OpenAI".
+    pub fn from_status(provider: &str, status: StatusCode, body: &str) -> Self {
+        let message = format!(
+            "{} API request failed (HTTP {}): {}",
+            provider,
+            status.as_u16(),
+            error_message(body)
+        );
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::AuthFailed { message },
+            StatusCode::NOT_FOUND => ProviderError::ModelNotFound { message },
+            StatusCode::TOO_MANY_REQUESTS => ProviderError::RateLimited {
+                message,
+                retry_after: None,
+            },
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => ProviderError::Timeout { message },
+            _ => ProviderError::Http {
+                status: status.as_u16(),
+                message,
+                retry_after: None,
+            },
+        }
+    }
+
+    /// Classifies a non-success response, reading its body and any
+    /// `Retry-After` header. Only the delay-seconds form of the header is
+    /// understood; an HTTP-date is ignored.
+    pub async fn from_response(provider: &str, res: Response) -> Self {
+        let status = res.status();
+        let delay = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        let body = res.text().await.unwrap_or_else(|e| e.to_string());
+
+        let mut err = Self::from_status(provider, status, &body);
+        if let ProviderError::RateLimited { retry_after, .. } | ProviderError::Http { retry_after, .. } = &mut err {
+            *retry_after = delay;
+        }
+        err
+    }
+
+    /// Classifies a transport-level failure talking to `endpoint`.
+    pub fn from_reqwest(provider: &str, endpoint: &str, err: &reqwest::Error) -> Self {
+        let root = StdError::source(err)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| err.to_string());
+        if err.is_connect() {
+            ProviderError::ConnectionRefused {
+                message: format!("Cannot connect to {} at {} — {}", provider, endpoint, root),
+            }
+        } else if err.is_timeout() {
+            ProviderError::Timeout {
+                message: format!("{} at {} timed out — {}", provider, endpoint, root),
+            }
+        } else if err.is_decode() {
+            ProviderError::BadResponse {
+                message: format!("Failed to deserialize {} response: {}", provider, root),
+            }
+        } else {
+            ProviderError::Other {
+                message: format!("{} request failed — {}", provider, root),
+            }
+        }
+    }
+
+    /// Whether the failure is likely temporary — the server was unreachable,
+    /// too slow, rate limiting or broken — so another attempt elsewhere or
+    /// later may succeed. Auth, config and bad-request errors are not.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ProviderError::ConnectionRefused { .. }
+            | ProviderError::Timeout { .. }
+            | ProviderError::RateLimited { .. } => true,
+            ProviderError::Http { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// How long the server asked us to wait before trying again, if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ProviderError::RateLimited { retry_after, .. } | ProviderError::Http { retry_after, .. } => {
+                retry_after.map(Duration::from_secs)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        ProviderError::Config {
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_response(message: impl Into<String>) -> Self {
+        ProviderError::BadResponse {
+            message: message.into(),
+        }
+    }
+}
+
+/// Pulls a readable message out of a JSON error body. Handles both the
+/// `{"error": {"message": ...}}` shape (OpenAI, Groq, Gemini) and Ollama's
+/// `{"error": "..."}`; falls back to the raw body.
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| {
+            v["error"]["message"]
+                .as_str()
+                .or_else(|| v["error"].as_str())
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_else(|| body.to_string())
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::ConnectionRefused { message }
+            | ProviderError::AuthFailed { message }
+            | ProviderError::RateLimited { message, .. }
+            | ProviderError::ModelNotFound { message }
+            | ProviderError::BadResponse { message }
+            | ProviderError::Timeout { message }
+            | ProviderError::Http { message, .. }
+            | ProviderError::Config { message }
+            | ProviderError::Other { message } => write!(f, "{}", message),
+            ProviderError::Cancelled => write!(f, "Request cancelled"),
+        }
+    }
+}
+
+impl StdError for ProviderError {}
+
+impl From<String> for ProviderError {
+    fn from(message: String) -> Self {
+        ProviderError::Other { message }
+    }
+}
@@ -1,27 +1,74 @@
-use crate::providers::base::Provider;
-use reqwest::Client;
+use crate::providers::base::{Completion, CompletionRequest, Provider, Usage};
+use crate::providers::error::ProviderError;
+use crate::providers::models::{fetch, sorted, ModelInfo};
+use crate::providers::stream::{for_each_line, sse_data};
+use reqwest::{Client, Response};
 use serde::Deserialize;
 
+// Streamed chunks may omit any of these (e.g. the final chunk only carries a
+// finishReason), so every level defaults to empty
 #[derive(Deserialize, Debug)]
 struct GeminiResponse {
+    #[serde(default)]
     candidates: Vec<Candidate>,
+    /// Running totals; the last streamed chunk has the final counts
+    #[serde(default, rename = "usageMetadata")]
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct UsageMetadata {
+    prompt_token_count: u64,
+    candidates_token_count: u64,
+    /// Thinking tokens are billed as output but counted separately
+    thoughts_token_count: u64,
+}
+
+impl From<UsageMetadata> for Usage {
+    fn from(m: UsageMetadata) -> Self {
+        Usage {
+            prompt_tokens: m.prompt_token_count,
+            completion_tokens: m.candidates_token_count + m.thoughts_token_count,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
 struct Candidate {
+    #[serde(default)]
     content: Content,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 struct Content {
+    #[serde(default)]
     parts: Vec<Part>,
 }
 
 #[derive(Deserialize, Debug)]
 struct Part {
+    #[serde(default)]
     text: String,
 }
 
+#[derive(Deserialize, Debug)]
+struct GeminiModelList {
+    #[serde(default)]
+    models: Vec<GeminiModel>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiModel {
+    /// `models/<id>`
+    name: String,
+    input_token_limit: Option<u64>,
+    thinking: Option<bool>,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
 pub struct GeminiProvider {
     client: Client,
     api_key: String,
@@ -31,9 +78,15 @@ pub struct GeminiProvider {
 }
 
 impl GeminiProvider {
-    pub fn new(api_key: Option<&str>, model: Option<&str>, base_url: Option<String>, thinking: Option<bool>) -> Self {
+    pub fn new(
+        client: Client,
+        api_key: Option<&str>,
+        model: Option<&str>,
+        base_url: Option<String>,
+        thinking: Option<bool>,
+    ) -> Self {
         Self {
-            client: Client::new(),
+            client,
             api_key: api_key.unwrap_or("").to_string(),
             model: model.unwrap_or("gemini-2.0-flash-lite").to_string(),
             base_url: base_url.unwrap_or_else(|| "https://generativelanguage.googleapis.com/v1beta".to_string()),
@@ -42,53 +95,142 @@ impl GeminiProvider {
     }
 }
 
-impl Provider for GeminiProvider {
-    async fn completion(&self, prompt: &str) -> Result<String, String> {
-        let url = format!(
-            "{}/models/{}:generateContent?key={}",
-            self.base_url, self.model, self.api_key
-        );
+impl GeminiProvider {
+    async fn send(&self, request: &CompletionRequest, stream: bool) -> Result<Response, ProviderError> {
+        let url = if stream {
+            format!(
+                "{}/models/{}:streamGenerateContent?alt=sse&key={}",
+                self.base_url, self.model, self.api_key
+            )
+        } else {
+            format!(
+                "{}/models/{}:generateContent?key={}",
+                self.base_url, self.model, self.api_key
+            )
+        };
 
         let mut body = serde_json::json!({
             "contents": [{
+                "role": "user",
                 "parts":[{
-                    "text": prompt
+                    "text": request.user
                 }]
             }]
         });
+        if let Some(system) = &request.system {
+            body["systemInstruction"] = serde_json::json!({ "parts": [{ "text": system }] });
+        }
 
+        let params = &request.params;
+        let mut generation_config = serde_json::json!({});
+        if let Some(t) = params.temperature {
+            generation_config["temperature"] = t.into();
+        }
+        if let Some(p) = params.top_p {
+            generation_config["topP"] = p.into();
+        }
+        if let Some(n) = params.max_tokens {
+            generation_config["maxOutputTokens"] = n.into();
+        }
+        if let Some(seed) = params.seed {
+            generation_config["seed"] = seed.into();
+        }
         // thinkingBudget: 0 disables thinking; omitting lets the model decide
         if self.thinking == Some(false) {
-            body["generationConfig"] = serde_json::json!({
-                "thinkingConfig": { "thinkingBudget": 0 }
-            });
+            generation_config["thinkingConfig"] = serde_json::json!({ "thinkingBudget": 0 });
+        }
+        if generation_config.as_object().is_some_and(|c| !c.is_empty()) {
+            body["generationConfig"] = generation_config;
         }
 
         let body = body;
 
         let res = self.client.post(&url).json(&body).send().await;
 
-        match res {
-            Ok(res) => {
-                if res.status().is_success() {
-                    let gemini_response = res.json::<GeminiResponse>().await;
-                    match gemini_response {
-                        Ok(data) => {
-                            if let Some(candidate) = data.candidates.get(0) {
-                                if let Some(part) = candidate.content.parts.get(0) {
-                                    return Ok(part.text.clone());
-                                }
-                            }
-                            Err("Failed to parse response from Gemini API".to_string())
-                        }
-                        Err(err) => Err(format!("Failed to deserialize Gemini response: {}", err)),
+        let res = res.map_err(|err| ProviderError::from_reqwest("Gemini", &self.base_url, &err))?;
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(ProviderError::from_response("Gemini", res).await)
+        }
+    }
+}
+
+impl Provider for GeminiProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        let res = self.send(request, false).await?;
+        let gemini_response = res.json::<GeminiResponse>().await;
+        match gemini_response {
+            Ok(data) => {
+                if let Some(candidate) = data.candidates.first() {
+                    if let Some(part) = candidate.content.parts.first() {
+                        return Ok(Completion {
+                            text: part.text.clone(),
+                            usage: data.usage_metadata.map(Usage::from),
+                        });
                     }
-                } else {
-                    let error_text = res.text().await.unwrap_or_else(|e| e.to_string());
-                    Err(format!("Gemini API request failed: {}", error_text))
                 }
+                Err(ProviderError::bad_response("Failed to parse response from Gemini API"))
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(ProviderError::from_reqwest("Gemini", &self.base_url, &err)),
         }
     }
-} 
\ No newline at end of file
+
+    async fn completion_stream<F>(&self, request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        let res = self.send(request, true).await?;
+        let mut output = String::new();
+        let mut usage = None;
+
+        // Each SSE event carries a partial GenerateContentResponse
+        for_each_line("Gemini", res, |line| {
+            let Some(data) = sse_data(line) else {
+                return Ok(true);
+            };
+            let chunk: GeminiResponse = serde_json::from_str(data)
+                .map_err(|e| ProviderError::bad_response(format!("Failed to deserialize Gemini response: {}", e)))?;
+            if let Some(metadata) = chunk.usage_metadata {
+                usage = Some(Usage::from(metadata));
+            }
+            let text: String = chunk
+                .candidates
+                .first()
+                .map(|c| c.content.parts.iter().map(|p| p.text.as_str()).collect())
+                .unwrap_or_default();
+            if !text.is_empty() {
+                output.push_str(&text);
+                return Ok(on_chunk(&text));
+            }
+            Ok(true)
+        })
+        .await?;
+
+        Ok(Completion { text: output, usage })
+    }
+
+    /// Lists only models that can generate content; embedding and other
+    /// models are left out.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let request = self
+            .client
+            .get(format!("{}/models?pageSize=1000&key={}", self.base_url, self.api_key));
+        let list: GeminiModelList = fetch("Gemini", &self.base_url, request).await?;
+        Ok(sorted(
+            list.models
+                .into_iter()
+                .filter(|m| m.supported_generation_methods.iter().any(|g| g == "generateContent"))
+                .map(|m| ModelInfo {
+                    id: m.name.strip_prefix("models/").unwrap_or(&m.name).to_string(),
+                    context_length: m.input_token_limit,
+                    supports_thinking: m.thinking,
+                })
+                .collect(),
+        ))
+    }
+}
@@ -1,5 +1,8 @@
-use crate::providers::base::Provider;
-use reqwest::Client;
+use crate::providers::base::{Completion, CompletionRequest, Provider, Usage};
+use crate::providers::error::ProviderError;
+use crate::providers::models::{openai_models, ModelInfo};
+use crate::providers::stream::{for_each_line, sse_data};
+use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize)]
@@ -8,6 +11,9 @@ struct GroqChatCompletionRequest<'a> {
     messages: Vec<GroqMessage<'a>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     reasoning_effort: Option<&'a str>,
+    stream: bool,
+    #[serde(flatten)]
+    sampling: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Serialize)]
@@ -19,6 +25,8 @@ struct GroqMessage<'a> {
 #[derive(Deserialize, Debug)]
 struct GroqChatCompletionResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -31,6 +39,33 @@ struct Message {
     content: String,
 }
 
+#[derive(Deserialize, Debug)]
+struct GroqChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    /// Only on the final chunk, and only if the server reports it
+    #[serde(default)]
+    usage: Option<Usage>,
+    /// Groq reports stream usage here rather than in `usage`
+    #[serde(default)]
+    x_groq: Option<XGroq>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChunkChoice {
+    delta: Delta,
+}
+
+#[derive(Deserialize, Debug)]
+struct Delta {
+    content: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct XGroq {
+    usage: Option<Usage>,
+}
+
 pub struct GroqProvider {
     client: Client,
     api_key: String,
@@ -40,9 +75,15 @@ pub struct GroqProvider {
 }
 
 impl GroqProvider {
-    pub fn new(api_key: Option<&str>, model: Option<&str>, base_url: Option<String>, thinking: Option<bool>) -> Self {
+    pub fn new(
+        client: Client,
+        api_key: Option<&str>,
+        model: Option<&str>,
+        base_url: Option<String>,
+        thinking: Option<bool>,
+    ) -> Self {
         Self {
-            client: Client::new(),
+            client,
             api_key: api_key.unwrap_or("").to_string(),
             model: model.unwrap_or("llama-3.1-8b-instant").to_string(),
             base_url: base_url.unwrap_or_else(|| "https://api.groq.com/openai/v1".to_string()),
@@ -51,14 +92,21 @@ impl GroqProvider {
     }
 }
 
-impl Provider for GroqProvider {
-    async fn completion(&self, prompt: &str) -> Result<String, String> {
+impl GroqProvider {
+    async fn send(&self, request: &CompletionRequest, stream: bool) -> Result<Response, ProviderError> {
         let url = format!("{}/chat/completions", self.base_url);
         
-        let messages = vec![GroqMessage {
+        let mut messages = Vec::new();
+        if let Some(system) = &request.system {
+            messages.push(GroqMessage {
+                role: "system",
+                content: system,
+            });
+        }
+        messages.push(GroqMessage {
             role: "user",
-            content: prompt,
-        }];
+            content: &request.user,
+        });
 
         // "none" disables reasoning for models that support it (e.g. Qwen3);
         // omitting the field uses the model's default (reasoning enabled)
@@ -71,6 +119,8 @@ impl Provider for GroqProvider {
             model: &self.model,
             messages,
             reasoning_effort,
+            stream,
+            sampling: request.params.openai_fields("max_completion_tokens"),
         };
 
         let res = self
@@ -81,26 +131,73 @@ impl Provider for GroqProvider {
             .send()
             .await;
 
-        match res {
-            Ok(res) => {
-                if res.status().is_success() {
-                    let response_body = res.json::<GroqChatCompletionResponse>().await;
-                    match response_body {
-                        Ok(data) => {
-                            if let Some(choice) = data.choices.get(0) {
-                                Ok(choice.message.content.clone())
-                            } else {
-                                Err("No content in Groq response".to_string())
-                            }
-                        }
-                        Err(err) => Err(format!("Failed to deserialize Groq response: {}", err)),
-                    }
+        let res = res.map_err(|err| ProviderError::from_reqwest("Groq", &self.base_url, &err))?;
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(ProviderError::from_response("Groq", res).await)
+        }
+    }
+}
+
+impl Provider for GroqProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        let res = self.send(request, false).await?;
+        let response_body = res.json::<GroqChatCompletionResponse>().await;
+        match response_body {
+            Ok(data) => {
+                if let Some(choice) = data.choices.first() {
+                    Ok(Completion {
+                        text: choice.message.content.clone(),
+                        usage: data.usage,
+                    })
                 } else {
-                    let error_text = res.text().await.unwrap_or_else(|e| e.to_string());
-                    Err(format!("Groq API request failed: {}", error_text))
+                    Err(ProviderError::bad_response("No content in Groq response"))
                 }
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(ProviderError::from_reqwest("Groq", &self.base_url, &err)),
         }
     }
-} 
\ No newline at end of file
+
+    async fn completion_stream<F>(&self, request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        let res = self.send(request, true).await?;
+        let mut output = String::new();
+        let mut usage = None;
+
+        for_each_line("Groq", res, |line| {
+            let Some(data) = sse_data(line) else {
+                return Ok(true);
+            };
+            let chunk: GroqChatCompletionChunk = serde_json::from_str(data)
+                .map_err(|e| ProviderError::bad_response(format!("Failed to deserialize Groq response: {}", e)))?;
+            if let Some(u) = chunk.usage.or(chunk.x_groq.and_then(|x| x.usage)) {
+                usage = Some(u);
+            }
+            if let Some(content) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+                if !content.is_empty() {
+                    output.push_str(content);
+                    return Ok(on_chunk(content));
+                }
+            }
+            Ok(true)
+        })
+        .await?;
+
+        Ok(Completion { text: output, usage })
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let request = self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .bearer_auth(&self.api_key);
+        openai_models("Groq", &self.base_url, request).await
+    }
+}
@@ -0,0 +1,100 @@
+use reqwest::{Client, NoProxy, Proxy};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::Runtime;
+use tauri_plugin_store::Store;
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 120;
+
+/// Network settings shared by every provider, saved as `CONNECT_TIMEOUT_SECS`,
+/// `REQUEST_TIMEOUT_SECS`, `PROXY_URL` and `NO_PROXY`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct HttpSettings {
+    pub connect_timeout_secs: u64,
+    /// Longest wait for the response or for the next chunk of its body; 0
+    /// means no limit. Not a cap on the whole request, so a long stream that
+    /// keeps producing tokens is never cut off.
+    pub request_timeout_secs: u64,
+    /// `http://`, `https://`, `socks5://` or `socks5h://` URL, credentials allowed
+    pub proxy_url: Option<String>,
+    /// Comma-separated hosts, domains or CIDR ranges that bypass the proxy
+    pub no_proxy: Option<String>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: DEFAULT_CONNECT_TIMEOUT_SECS,
+            request_timeout_secs: DEFAULT_REQUEST_TIMEOUT_SECS,
+            proxy_url: None,
+            no_proxy: None,
+        }
+    }
+}
+
+impl HttpSettings {
+    pub fn from_store<R: Runtime>(store: &Store<R>) -> Self {
+        let string = |key: &str| {
+            store
+                .get(key)
+                .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
+                .filter(|s| !s.is_empty())
+        };
+        let secs = |key: &str, default: u64| store.get(key).and_then(|v| v.as_u64()).unwrap_or(default);
+
+        Self {
+            connect_timeout_secs: secs("CONNECT_TIMEOUT_SECS", DEFAULT_CONNECT_TIMEOUT_SECS),
+            request_timeout_secs: secs("REQUEST_TIMEOUT_SECS", DEFAULT_REQUEST_TIMEOUT_SECS),
+            proxy_url: string("PROXY_URL"),
+            no_proxy: string("NO_PROXY"),
+        }
+    }
+
+    pub fn build_client(&self) -> Result<Client, String> {
+        let mut builder = Client::builder().connect_timeout(Duration::from_secs(self.connect_timeout_secs.max(1)));
+        if self.request_timeout_secs > 0 {
+            builder = builder.read_timeout(Duration::from_secs(self.request_timeout_secs));
+        }
+        if let Some(url) = &self.proxy_url {
+            let proxy = Proxy::all(url)
+                .map_err(|e| format!("Invalid proxy URL \"{}\": {}", url, e))?
+                .no_proxy(self.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }
+}
+
+/// The HTTP client every provider shares, held in Tauri state so connections
+/// are pooled across requests. Swapped out when the network settings change.
+pub struct SharedClient(RwLock<Client>);
+
+impl SharedClient {
+    /// Builds the client from saved settings. A broken proxy setting must not
+    /// stop the app from starting, so it falls back to a direct connection.
+    pub fn from_store<R: Runtime>(store: &Store<R>) -> Self {
+        let settings = HttpSettings::from_store(store);
+        let client = settings.build_client().unwrap_or_else(|e| {
+            eprintln!("{}; connecting without a proxy", e);
+            HttpSettings {
+                proxy_url: None,
+                ..settings
+            }
+            .build_client()
+            .unwrap_or_default()
+        });
+        Self(RwLock::new(client))
+    }
+
+    /// A handle to the client; cheap, as clones share the connection pool.
+    pub fn get(&self) -> Client {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn replace(&self, client: Client) {
+        *self.0.write().unwrap() = client;
+    }
+}
@@ -1,5 +1,14 @@
+pub mod anthropic;
 pub mod base;
+pub mod error;
 pub mod gemini;
 pub mod groq;
+pub mod http;
+pub mod models;
 pub mod ollama;
 pub mod openai;
+pub mod openai_compatible;
+pub mod retry;
+pub(crate) mod stream;
+#[cfg(test)]
+pub(crate) mod test_server;
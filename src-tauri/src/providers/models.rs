@@ -0,0 +1,77 @@
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::error::ProviderError;
+
+/// A model offered by a provider. Fields the provider does not report are
+/// left out rather than guessed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub id: String,
+    /// Maximum input tokens
+    pub context_length: Option<u64>,
+    pub supports_thinking: Option<bool>,
+}
+
+/// A model list is small, so unlike a completion it gets a deadline for the
+/// whole request.
+const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends a listing request and decodes the JSON body, classifying failures
+/// the same way completions do.
+pub(crate) async fn fetch<T: DeserializeOwned>(
+    provider: &str,
+    endpoint: &str,
+    request: RequestBuilder,
+) -> Result<T, ProviderError> {
+    let res = request
+        .timeout(LIST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|err| ProviderError::from_reqwest(provider, endpoint, &err))?;
+    if !res.status().is_success() {
+        return Err(ProviderError::from_response(provider, res).await);
+    }
+    res.json::<T>()
+        .await
+        .map_err(|err| ProviderError::from_reqwest(provider, endpoint, &err))
+}
+
+#[derive(Deserialize)]
+struct OpenAIModelList {
+    data: Vec<OpenAIModel>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIModel {
+    id: String,
+    // Groq says context_window, vLLM max_model_len; OpenAI itself says nothing
+    #[serde(default, alias = "context_length", alias = "max_model_len")]
+    context_window: Option<u64>,
+}
+
+/// Lists models from an OpenAI-style `GET /models` endpoint.
+pub(crate) async fn openai_models(
+    provider: &str,
+    endpoint: &str,
+    request: RequestBuilder,
+) -> Result<Vec<ModelInfo>, ProviderError> {
+    let list: OpenAIModelList = fetch(provider, endpoint, request).await?;
+    Ok(sorted(
+        list.data
+            .into_iter()
+            .map(|m| ModelInfo {
+                id: m.id,
+                context_length: m.context_window,
+                supports_thinking: None,
+            })
+            .collect(),
+    ))
+}
+
+pub(crate) fn sorted(mut models: Vec<ModelInfo>) -> Vec<ModelInfo> {
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models
+}
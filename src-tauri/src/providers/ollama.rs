@@ -1,20 +1,85 @@
-use crate::providers::base::Provider;
-use reqwest::Client;
+use crate::providers::base::{Completion, CompletionRequest, GenerationParams, Provider, Usage};
+use crate::providers::error::ProviderError;
+use crate::providers::models::{fetch, sorted, ModelInfo};
+use crate::providers::stream::for_each_line;
+use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
-use std::error::Error as StdError;
+use std::collections::HashMap;
 
 #[derive(Serialize)]
-struct OllamaRequest<'a> {
+struct OllamaChatRequest<'a> {
     model: &'a str,
-    prompt: &'a str,
+    messages: serde_json::Value,
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     think: Option<bool>,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    options: serde_json::Map<String, serde_json::Value>,
 }
 
+#[derive(Deserialize, Default)]
+struct OllamaMessage {
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct OllamaChatResponse {
+    message: OllamaMessage,
+    #[serde(flatten)]
+    counts: OllamaCounts,
+}
+
+/// Token counts, present once the response is done.
+#[derive(Deserialize, Default)]
+struct OllamaCounts {
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+}
+
+impl OllamaCounts {
+    fn usage(&self) -> Option<Usage> {
+        match (self.prompt_eval_count, self.eval_count) {
+            (None, None) => None,
+            (prompt, completion) => Some(Usage {
+                prompt_tokens: prompt.unwrap_or(0),
+                completion_tokens: completion.unwrap_or(0),
+            }),
+        }
+    }
+}
+
+/// One line of the NDJSON body returned when `stream` is true.
+#[derive(Deserialize)]
+struct OllamaStreamLine {
+    #[serde(default)]
+    message: OllamaMessage,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+    #[serde(flatten)]
+    counts: OllamaCounts,
+}
+
+#[derive(Deserialize)]
+struct OllamaTags {
+    #[serde(default)]
+    models: Vec<OllamaTag>,
+}
+
+#[derive(Deserialize)]
+struct OllamaTag {
+    name: String,
+}
+
+/// The parts of `/api/show` used for model listing. `capabilities` is
+/// missing on Ollama versions before 0.6.4.
 #[derive(Deserialize)]
-struct OllamaResponse {
-    response: String,
+struct OllamaShow {
+    #[serde(default)]
+    capabilities: Vec<String>,
+    #[serde(default)]
+    model_info: HashMap<String, serde_json::Value>,
 }
 
 pub struct OllamaProvider {
@@ -25,12 +90,12 @@ pub struct OllamaProvider {
 }
 
 impl OllamaProvider {
-    pub fn new(endpoint: Option<String>, model: Option<String>, thinking: Option<bool>) -> Self {
+    pub fn new(client: Client, endpoint: Option<String>, model: Option<String>, thinking: Option<bool>) -> Self {
         let host = endpoint.unwrap_or_else(|| String::from("http://localhost:11434"));
         let default_model = String::from("gemma3");
 
         Self {
-            client: Client::new(),
+            client,
             model: model.unwrap_or(default_model),
             host,
             thinking,
@@ -38,55 +103,139 @@ impl OllamaProvider {
     }
 }
 
-impl Provider for OllamaProvider {
-    async fn completion(&self, text: &str) -> Result<String, String> {
-        let url = format!("{}/api/generate", self.host);
+/// Sampling settings in Ollama's `options` names.
+fn ollama_options(params: &GenerationParams) -> serde_json::Map<String, serde_json::Value> {
+    let mut options = serde_json::Map::new();
+    if let Some(t) = params.temperature {
+        options.insert("temperature".into(), t.into());
+    }
+    if let Some(p) = params.top_p {
+        options.insert("top_p".into(), p.into());
+    }
+    if let Some(n) = params.max_tokens {
+        options.insert("num_predict".into(), n.into());
+    }
+    if let Some(seed) = params.seed {
+        options.insert("seed".into(), seed.into());
+    }
+    options
+}
+
+impl OllamaProvider {
+    /// Sends a chat request and classifies transport and HTTP failures.
+    /// Only successful responses are returned.
+    async fn send(&self, request: &CompletionRequest, stream: bool) -> Result<Response, ProviderError> {
+        let url = format!("{}/api/chat", self.host);
         // Only send think:false to disable it; omit the field for models that don't support it
         let think = match self.thinking {
             Some(false) => Some(false),
             _ => None,
         };
-        let request_body = OllamaRequest {
+        let request_body = OllamaChatRequest {
             model: &self.model,
-            prompt: text,
-            stream: false,
+            messages: request.chat_messages(),
+            stream,
             think,
+            options: ollama_options(&request.params),
         };
 
-        let res = self.client.post(&url).json(&request_body).send().await;
-
-        match res {
-            Ok(res) => {
-                if res.status().is_success() {
-                    let ollama_response = res.json::<OllamaResponse>().await;
-                    match ollama_response {
-                        Ok(data) => Ok(data.response),
-                        Err(err) => Err(format!("Failed to deserialize Ollama response: {}", err)),
-                    }
-                } else {
-                    let status = res.status();
-                    let error_text = res.text().await.unwrap_or_default();
-                    // Try to extract the "error" field from Ollama's JSON error response
-                    let msg = serde_json::from_str::<serde_json::Value>(&error_text)
-                        .ok()
-                        .and_then(|v| v["error"].as_str().map(|s| s.to_string()))
-                        .unwrap_or(error_text);
-                    Err(format!("Ollama error (HTTP {}): {}", status.as_u16(), msg))
+        let res = self
+            .client
+            .post(&url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|err| ProviderError::from_reqwest("Ollama", &self.host, &err))?;
+
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(ProviderError::from_response("Ollama", res).await)
+        }
+    }
+}
+
+impl Provider for OllamaProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        let res = self.send(request, false).await?;
+        match res.json::<OllamaChatResponse>().await {
+            Ok(data) => Ok(Completion {
+                usage: data.counts.usage(),
+                text: data.message.content,
+            }),
+            Err(err) => Err(ProviderError::from_reqwest("Ollama", &self.host, &err)),
+        }
+    }
+
+    async fn completion_stream<F>(&self, request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        let res = self.send(request, true).await?;
+        let mut output = String::new();
+        let mut usage = None;
+
+        for_each_line("Ollama", res, |line| {
+            let data: OllamaStreamLine = serde_json::from_str(line).map_err(|e| {
+                ProviderError::bad_response(format!("Failed to deserialize Ollama response: {}", e))
+            })?;
+            if let Some(error) = data.error {
+                return Err(ProviderError::Other {
+                    message: format!("Ollama error: {}", error),
+                });
+            }
+            let content = &data.message.content;
+            if !content.is_empty() {
+                output.push_str(content);
+                if !on_chunk(content) {
+                    return Ok(false);
                 }
             }
-            Err(err) => {
-                let root = StdError::source(&err)
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| err.to_string());
-                let msg = if err.is_connect() {
-                    format!("Cannot connect to Ollama at {} — {}", self.host, root)
-                } else if err.is_timeout() {
-                    format!("Ollama at {} timed out — {}", self.host, root)
-                } else {
-                    format!("Ollama request failed — {}", root)
-                };
-                Err(msg)
+            if data.done {
+                usage = data.counts.usage();
             }
+            Ok(!data.done)
+        })
+        .await?;
+
+        Ok(Completion { text: output, usage })
+    }
+
+    /// Lists installed models. `/api/tags` only has names, so each model is
+    /// looked up with `/api/show`; a model that fails the lookup is still
+    /// listed, without details.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let tags: OllamaTags = fetch("Ollama", &self.host, self.client.get(format!("{}/api/tags", self.host))).await?;
+
+        let mut models = Vec::with_capacity(tags.models.len());
+        for tag in tags.models {
+            let request = self
+                .client
+                .post(format!("{}/api/show", self.host))
+                .json(&serde_json::json!({ "model": tag.name }));
+            let show = fetch::<OllamaShow>("Ollama", &self.host, request).await.ok();
+
+            // The key is prefixed with the architecture, e.g. "gemma3.context_length"
+            let context_length = show.as_ref().and_then(|s| {
+                s.model_info
+                    .iter()
+                    .find(|(key, _)| key.ends_with(".context_length"))
+                    .and_then(|(_, value)| value.as_u64())
+            });
+            let supports_thinking = show
+                .filter(|s| !s.capabilities.is_empty())
+                .map(|s| s.capabilities.iter().any(|c| c == "thinking"));
+
+            models.push(ModelInfo {
+                id: tag.name,
+                context_length,
+                supports_thinking,
+            });
         }
+        Ok(sorted(models))
     }
 }
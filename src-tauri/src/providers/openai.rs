@@ -1,10 +1,15 @@
-use crate::providers::base::Provider;
-use reqwest::Client;
+use crate::providers::base::{Completion, CompletionRequest, Provider, Usage};
+use crate::providers::error::ProviderError;
+use crate::providers::models::{openai_models, ModelInfo};
+use crate::providers::stream::{for_each_line, sse_data};
+use reqwest::{Client, Response};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
 struct OpenAIChatCompletionResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -17,6 +22,25 @@ struct Message {
     content: String,
 }
 
+#[derive(Deserialize, Debug)]
+struct OpenAIChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    /// Only on the final chunk, and only if the server reports it
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChunkChoice {
+    delta: Delta,
+}
+
+#[derive(Deserialize, Debug)]
+struct Delta {
+    content: Option<String>,
+}
+
 pub struct OpenAIProvider {
     client: Client,
     api_key: String,
@@ -26,9 +50,15 @@ pub struct OpenAIProvider {
 }
 
 impl OpenAIProvider {
-    pub fn new(api_key: Option<&str>, model: Option<&str>, base_url: Option<String>, thinking: Option<bool>) -> Self {
+    pub fn new(
+        client: Client,
+        api_key: Option<&str>,
+        model: Option<&str>,
+        base_url: Option<String>,
+        thinking: Option<bool>,
+    ) -> Self {
         Self {
-            client: Client::new(),
+            client,
             api_key: api_key.unwrap_or("").to_string(),
             model: model.unwrap_or("gpt-4.1-nano").to_string(),
             base_url: base_url.unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
@@ -37,23 +67,27 @@ impl OpenAIProvider {
     }
 }
 
-impl Provider for OpenAIProvider {
-    async fn completion(&self, prompt: &str) -> Result<String, String> {
+impl OpenAIProvider {
+    async fn send(&self, request: &CompletionRequest, stream: bool) -> Result<Response, ProviderError> {
         let url = format!("{}/chat/completions", self.base_url);
 
         let mut body = serde_json::json!({
             "model": self.model,
-            "messages": [{ "role": "user", "content": prompt }]
+            "messages": request.chat_messages(),
+            "stream": stream
         });
+        for (field, value) in request.params.openai_fields("max_completion_tokens") {
+            body[field] = value;
+        }
+        // Streams only report usage when asked, in an extra final chunk
+        if stream {
+            body["stream_options"] = serde_json::json!({ "include_usage": true });
+        }
 
+        // Turn reasoning off on reasoning models. Self-hosted servers with a
+        // different switch use the openai-compatible provider instead.
         if self.thinking == Some(false) {
-            if self.base_url.contains("api.openai.com") {
-                // Official OpenAI: disable reasoning on o-series models
-                body["reasoning"] = serde_json::json!({ "effort": "none" });
-            } else {
-                // llama.cpp and other OpenAI-compatible servers
-                body["chat_template_kwargs"] = serde_json::json!({ "enable_thinking": false });
-            }
+            body["reasoning_effort"] = serde_json::json!("none");
         }
 
         let res = self
@@ -64,26 +98,73 @@ impl Provider for OpenAIProvider {
             .send()
             .await;
 
-        match res {
-            Ok(res) => {
-                if res.status().is_success() {
-                    let response_body = res.json::<OpenAIChatCompletionResponse>().await;
-                    match response_body {
-                        Ok(data) => {
-                            if let Some(choice) = data.choices.get(0) {
-                                Ok(choice.message.content.clone())
-                            } else {
-                                Err("No content in OpenAI response".to_string())
-                            }
-                        }
-                        Err(err) => Err(format!("Failed to deserialize OpenAI response: {}", err)),
-                    }
+        let res = res.map_err(|err| ProviderError::from_reqwest("OpenAI", &self.base_url, &err))?;
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(ProviderError::from_response("OpenAI", res).await)
+        }
+    }
+}
+
+impl Provider for OpenAIProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        let res = self.send(request, false).await?;
+        let response_body = res.json::<OpenAIChatCompletionResponse>().await;
+        match response_body {
+            Ok(data) => {
+                if let Some(choice) = data.choices.first() {
+                    Ok(Completion {
+                        text: choice.message.content.clone(),
+                        usage: data.usage,
+                    })
                 } else {
-                    let error_text = res.text().await.unwrap_or_else(|e| e.to_string());
-                    Err(format!("OpenAI API request failed: {}", error_text))
+                    Err(ProviderError::bad_response("No content in OpenAI response"))
                 }
             }
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(ProviderError::from_reqwest("OpenAI", &self.base_url, &err)),
         }
     }
+
+    async fn completion_stream<F>(&self, request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        let res = self.send(request, true).await?;
+        let mut output = String::new();
+        let mut usage = None;
+
+        for_each_line("OpenAI", res, |line| {
+            let Some(data) = sse_data(line) else {
+                return Ok(true);
+            };
+            let chunk: OpenAIChatCompletionChunk = serde_json::from_str(data)
+                .map_err(|e| ProviderError::bad_response(format!("Failed to deserialize OpenAI response: {}", e)))?;
+            if chunk.usage.is_some() {
+                usage = chunk.usage;
+            }
+            if let Some(content) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+                if !content.is_empty() {
+                    output.push_str(content);
+                    return Ok(on_chunk(content));
+                }
+            }
+            Ok(true)
+        })
+        .await?;
+
+        Ok(Completion { text: output, usage })
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let request = self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .bearer_auth(&self.api_key);
+        openai_models("OpenAI", &self.base_url, request).await
+    }
 }
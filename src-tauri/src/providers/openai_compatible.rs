@@ -0,0 +1,388 @@
+use crate::providers::base::{Completion, CompletionRequest, Provider, Usage};
+use crate::providers::error::ProviderError;
+use crate::providers::models::{openai_models, ModelInfo};
+use crate::providers::stream::{for_each_line, sse_data};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+struct ChatCompletionResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize, Debug)]
+struct Message {
+    content: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    /// Only on the final chunk, and only if the server reports it
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChunkChoice {
+    delta: Delta,
+}
+
+#[derive(Deserialize, Debug)]
+struct Delta {
+    content: Option<String>,
+}
+
+/// How the API key is sent. Written in settings as `bearer` (default),
+/// `none`, or `header:<Name>` for gateways that expect e.g. `api-key`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthScheme {
+    Bearer,
+    None,
+    Header(String),
+}
+
+impl AuthScheme {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(str::trim).filter(|v| !v.is_empty()) {
+            None | Some("bearer") => Ok(AuthScheme::Bearer),
+            Some("none") => Ok(AuthScheme::None),
+            Some(v) => match v.strip_prefix("header:").map(str::trim) {
+                Some(name) if HeaderName::from_bytes(name.as_bytes()).is_ok() => {
+                    Ok(AuthScheme::Header(name.to_string()))
+                }
+                _ => Err(format!(
+                    "Unknown auth scheme \"{}\" (expected bearer, none or header:<Name>)",
+                    v
+                )),
+            },
+        }
+    }
+}
+
+/// Which request field turns reasoning off when `thinking` is false. Servers
+/// disagree, so this is set explicitly instead of guessed from the URL.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReasoningDialect {
+    /// `chat_template_kwargs: {enable_thinking: false}` — llama.cpp, vLLM
+    ChatTemplateKwargs,
+    /// `reasoning_effort: "none"` — LiteLLM and other OpenAI-style gateways
+    ReasoningEffort,
+    /// Send nothing; the server has no switch
+    None,
+}
+
+impl ReasoningDialect {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(str::trim).filter(|v| !v.is_empty()) {
+            None | Some("chat_template_kwargs") => Ok(ReasoningDialect::ChatTemplateKwargs),
+            Some("reasoning_effort") => Ok(ReasoningDialect::ReasoningEffort),
+            Some("none") => Ok(ReasoningDialect::None),
+            Some(v) => Err(format!(
+                "Unknown reasoning dialect \"{}\" (expected chat_template_kwargs, reasoning_effort or none)",
+                v
+            )),
+        }
+    }
+}
+
+/// Converts user-supplied header pairs, rejecting names or values that are
+/// not valid HTTP.
+pub fn parse_extra_headers(headers: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("Invalid header name \"{}\"", name))?;
+        let header_value = HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("Invalid value for header \"{}\"", name))?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+/// A self-hosted or third-party server speaking the OpenAI chat-completions
+/// protocol (LiteLLM, vLLM, LM Studio, llama.cpp server, ...).
+pub struct OpenAICompatibleProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    base_url: String,
+    thinking: Option<bool>,
+    extra_headers: HeaderMap,
+    auth_scheme: AuthScheme,
+    reasoning_dialect: ReasoningDialect,
+}
+
+impl OpenAICompatibleProvider {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client: Client,
+        api_key: Option<&str>,
+        model: &str,
+        base_url: String,
+        thinking: Option<bool>,
+        extra_headers: HeaderMap,
+        auth_scheme: AuthScheme,
+        reasoning_dialect: ReasoningDialect,
+    ) -> Self {
+        Self {
+            client,
+            api_key: api_key.unwrap_or("").to_string(),
+            model: model.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            thinking,
+            extra_headers,
+            auth_scheme,
+            reasoning_dialect,
+        }
+    }
+
+    /// Adds the configured extra headers and credentials.
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = request.headers(self.extra_headers.clone());
+        match &self.auth_scheme {
+            AuthScheme::Bearer => request.bearer_auth(&self.api_key),
+            AuthScheme::Header(name) => request.header(name.as_str(), &self.api_key),
+            AuthScheme::None => request,
+        }
+    }
+
+    async fn send(&self, request: &CompletionRequest, stream: bool) -> Result<Response, ProviderError> {
+        let url = format!("{}/chat/completions", self.base_url);
+
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "messages": request.chat_messages(),
+            "stream": stream
+        });
+        for (field, value) in request.params.openai_fields("max_tokens") {
+            body[field] = value;
+        }
+
+        if self.thinking == Some(false) {
+            match self.reasoning_dialect {
+                ReasoningDialect::ChatTemplateKwargs => {
+                    body["chat_template_kwargs"] = serde_json::json!({ "enable_thinking": false });
+                }
+                ReasoningDialect::ReasoningEffort => {
+                    body["reasoning_effort"] = serde_json::json!("none");
+                }
+                ReasoningDialect::None => {}
+            }
+        }
+
+        let res = self
+            .authorize(self.client.post(&url))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| ProviderError::from_reqwest("OpenAI-compatible server", &self.base_url, &err))?;
+
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(ProviderError::from_response("OpenAI-compatible server", res).await)
+        }
+    }
+}
+
+impl Provider for OpenAICompatibleProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        let res = self.send(request, false).await?;
+        let data = res
+            .json::<ChatCompletionResponse>()
+            .await
+            .map_err(|err| ProviderError::from_reqwest("OpenAI-compatible server", &self.base_url, &err))?;
+        match data.choices.first() {
+            Some(choice) => Ok(Completion {
+                text: choice.message.content.clone(),
+                usage: data.usage,
+            }),
+            None => Err(ProviderError::bad_response("No content in OpenAI-compatible response")),
+        }
+    }
+
+    async fn completion_stream<F>(&self, request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        let res = self.send(request, true).await?;
+        let mut output = String::new();
+        let mut usage = None;
+
+        for_each_line("OpenAI-compatible server", res, |line| {
+            let Some(data) = sse_data(line) else {
+                return Ok(true);
+            };
+            let chunk: ChatCompletionChunk = serde_json::from_str(data).map_err(|e| {
+                ProviderError::bad_response(format!("Failed to deserialize OpenAI-compatible response: {}", e))
+            })?;
+            if chunk.usage.is_some() {
+                usage = chunk.usage;
+            }
+            if let Some(content) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+                if !content.is_empty() {
+                    output.push_str(content);
+                    return Ok(on_chunk(content));
+                }
+            }
+            Ok(true)
+        })
+        .await?;
+
+        Ok(Completion { text: output, usage })
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let request = self.authorize(self.client.get(format!("{}/models", self.base_url)));
+        openai_models("OpenAI-compatible server", &self.base_url, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::test_server::{MockResponse, MockServer};
+    use serde_json::json;
+
+    fn provider(
+        server: &MockServer,
+        thinking: Option<bool>,
+        extra_headers: HeaderMap,
+        auth_scheme: AuthScheme,
+        reasoning_dialect: ReasoningDialect,
+    ) -> OpenAICompatibleProvider {
+        OpenAICompatibleProvider::new(
+            Client::new(),
+            Some("test-key"),
+            "local-model",
+            format!("{}/v1", server.url),
+            thinking,
+            extra_headers,
+            auth_scheme,
+            reasoning_dialect,
+        )
+    }
+
+    fn answer() -> MockResponse {
+        MockResponse::json(200, json!({ "choices": [{ "message": { "content": "Hi" } }] }))
+    }
+
+    async fn send_once(
+        thinking: Option<bool>,
+        extra_headers: HeaderMap,
+        auth_scheme: AuthScheme,
+        reasoning_dialect: ReasoningDialect,
+    ) -> crate::providers::test_server::RecordedRequest {
+        let server = MockServer::start(vec![answer()]).await;
+        let request = CompletionRequest::new("Translate", "Hallo");
+        let completion = provider(&server, thinking, extra_headers, auth_scheme, reasoning_dialect)
+            .completion(&request)
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "Hi");
+        server.requests().remove(0)
+    }
+
+    #[tokio::test]
+    async fn sends_a_bearer_key_by_default() {
+        let sent = send_once(None, HeaderMap::new(), AuthScheme::Bearer, ReasoningDialect::ChatTemplateKwargs).await;
+        assert_eq!(sent.path, "/v1/chat/completions");
+        assert_eq!(sent.header("authorization"), Some("Bearer test-key"));
+        assert_eq!(sent.json()["model"], "local-model");
+    }
+
+    #[tokio::test]
+    async fn sends_the_key_in_a_named_header() {
+        let scheme = AuthScheme::parse(Some("header:api-key")).unwrap();
+        let sent = send_once(None, HeaderMap::new(), scheme, ReasoningDialect::ChatTemplateKwargs).await;
+        assert_eq!(sent.header("api-key"), Some("test-key"));
+        assert_eq!(sent.header("authorization"), None);
+    }
+
+    #[tokio::test]
+    async fn sends_no_key_without_auth() {
+        let sent = send_once(None, HeaderMap::new(), AuthScheme::None, ReasoningDialect::ChatTemplateKwargs).await;
+        assert_eq!(sent.header("authorization"), None);
+    }
+
+    #[tokio::test]
+    async fn sends_extra_headers() {
+        let headers = parse_extra_headers(&HashMap::from([
+            ("X-Team".to_string(), " translators ".to_string()),
+            ("OpenAI-Organization".to_string(), "org-1".to_string()),
+        ]))
+        .unwrap();
+        let sent = send_once(None, headers, AuthScheme::Bearer, ReasoningDialect::ChatTemplateKwargs).await;
+        assert_eq!(sent.header("x-team"), Some("translators"));
+        assert_eq!(sent.header("openai-organization"), Some("org-1"));
+        assert_eq!(sent.header("authorization"), Some("Bearer test-key"));
+    }
+
+    #[tokio::test]
+    async fn disabling_thinking_uses_the_configured_dialect() {
+        let sent = send_once(Some(false), HeaderMap::new(), AuthScheme::Bearer, ReasoningDialect::ChatTemplateKwargs).await;
+        let body = sent.json();
+        assert_eq!(body["chat_template_kwargs"]["enable_thinking"], false);
+        assert!(body.get("reasoning_effort").is_none());
+
+        let sent = send_once(Some(false), HeaderMap::new(), AuthScheme::Bearer, ReasoningDialect::ReasoningEffort).await;
+        let body = sent.json();
+        assert_eq!(body["reasoning_effort"], "none");
+        assert!(body.get("chat_template_kwargs").is_none());
+
+        let sent = send_once(Some(false), HeaderMap::new(), AuthScheme::Bearer, ReasoningDialect::None).await;
+        let body = sent.json();
+        assert!(body.get("reasoning_effort").is_none());
+        assert!(body.get("chat_template_kwargs").is_none());
+    }
+
+    #[tokio::test]
+    async fn leaves_thinking_alone_unless_disabled() {
+        let sent = send_once(Some(true), HeaderMap::new(), AuthScheme::Bearer, ReasoningDialect::ReasoningEffort).await;
+        assert!(sent.json().get("reasoning_effort").is_none());
+    }
+
+    #[test]
+    fn parses_auth_schemes() {
+        assert_eq!(AuthScheme::parse(None), Ok(AuthScheme::Bearer));
+        assert_eq!(AuthScheme::parse(Some(" ")), Ok(AuthScheme::Bearer));
+        assert_eq!(AuthScheme::parse(Some("none")), Ok(AuthScheme::None));
+        assert_eq!(
+            AuthScheme::parse(Some("header: X-Api-Key")),
+            Ok(AuthScheme::Header("X-Api-Key".to_string()))
+        );
+        assert!(AuthScheme::parse(Some("header:bad name")).is_err());
+        assert!(AuthScheme::parse(Some("basic")).is_err());
+    }
+
+    #[test]
+    fn parses_reasoning_dialects() {
+        assert_eq!(ReasoningDialect::parse(None), Ok(ReasoningDialect::ChatTemplateKwargs));
+        assert_eq!(ReasoningDialect::parse(Some("reasoning_effort")), Ok(ReasoningDialect::ReasoningEffort));
+        assert_eq!(ReasoningDialect::parse(Some("none")), Ok(ReasoningDialect::None));
+        assert!(ReasoningDialect::parse(Some("think_off")).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_extra_headers() {
+        let bad_name = HashMap::from([("Bad Header".to_string(), "x".to_string())]);
+        assert!(parse_extra_headers(&bad_name).unwrap_err().contains("header name"));
+        let bad_value = HashMap::from([("X-Ok".to_string(), "line\nbreak".to_string())]);
+        assert!(parse_extra_headers(&bad_value).unwrap_err().contains("X-Ok"));
+    }
+}
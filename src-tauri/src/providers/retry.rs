@@ -0,0 +1,319 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tauri::Runtime;
+use tauri_plugin_store::Store;
+
+use super::base::{Completion, CompletionRequest, Provider};
+use super::error::ProviderError;
+use super::models::ModelInfo;
+
+/// How often and how patiently a transient failure is retried. Read from the
+/// store keys `RETRY_ATTEMPTS` and `RETRY_BASE_DELAY_MS`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total tries, including the first; 1 disables retrying
+    pub attempts: u32,
+    pub base_delay: Duration,
+    /// Upper bound on any single wait. A `Retry-After` longer than this is
+    /// not waited out; the error is returned instead.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(20),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_store<R: Runtime>(store: &Store<R>) -> Self {
+        let default = Self::default();
+        Self {
+            attempts: store
+                .get("RETRY_ATTEMPTS")
+                .and_then(|v| v.as_u64())
+                .map(|n| n.clamp(1, 10) as u32)
+                .unwrap_or(default.attempts),
+            base_delay: store
+                .get("RETRY_BASE_DELAY_MS")
+                .and_then(|v| v.as_u64())
+                .map(Duration::from_millis)
+                .unwrap_or(default.base_delay),
+            ..default
+        }
+    }
+
+    /// The wait before retry number `retry` (0-based) after `err`, or `None`
+    /// to give up. The server's `Retry-After` wins; otherwise the delay
+    /// doubles each time, with the upper half randomised so clients that
+    /// failed together do not retry together.
+    fn delay(&self, retry: u32, err: &ProviderError) -> Option<Duration> {
+        if retry + 1 >= self.attempts || !err.is_transient() {
+            return None;
+        }
+        if let Some(wait) = err.retry_after() {
+            return (wait <= self.max_delay).then_some(wait);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << retry.min(16))
+            .min(self.max_delay);
+        let half = backoff / 2;
+        Some(half + half.mul_f64(jitter()))
+    }
+}
+
+/// A number in `[0, 1)`. Seeded per call from the std hasher's random keys,
+/// which is plenty for spreading retries out.
+fn jitter() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Wraps a provider so transient failures (unreachable, timeout, 429, 5xx)
+/// are retried according to a [`RetryPolicy`].
+pub struct Retrying<P> {
+    inner: P,
+    policy: RetryPolicy,
+}
+
+impl<P: Provider> Retrying<P> {
+    pub fn new(inner: P, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl<P: Provider> Provider for Retrying<P> {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn completion(&self, request: &CompletionRequest) -> Result<Completion, ProviderError> {
+        let mut retry = 0;
+        loop {
+            match self.inner.completion(request).await {
+                Ok(completion) => return Ok(completion),
+                Err(err) => match self.policy.delay(retry, &err) {
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => return Err(err),
+                },
+            }
+            retry += 1;
+        }
+    }
+
+    /// Retries only while nothing has been passed to `on_chunk`; once text
+    /// has reached the caller a retry would repeat it.
+    async fn completion_stream<F>(&self, request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+    where
+        F: FnMut(&str) -> bool + Send,
+    {
+        let mut retry = 0;
+        loop {
+            let mut received = false;
+            let result = self
+                .inner
+                .completion_stream(request, |delta| {
+                    received = true;
+                    on_chunk(delta)
+                })
+                .await;
+            match result {
+                Ok(completion) => return Ok(completion),
+                Err(err) if received => return Err(err),
+                Err(err) => match self.policy.delay(retry, &err) {
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => return Err(err),
+                },
+            }
+            retry += 1;
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        self.inner.list_models().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::anthropic::AnthropicProvider;
+    use crate::providers::test_server::{MockResponse, MockServer};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn timeout() -> ProviderError {
+        ProviderError::Timeout {
+            message: "timed out".to_string(),
+        }
+    }
+
+    fn rate_limited(retry_after: Option<u64>) -> ProviderError {
+        ProviderError::RateLimited {
+            message: "slow down".to_string(),
+            retry_after,
+        }
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+        }
+    }
+
+    #[test]
+    fn gives_up_when_attempts_are_used() {
+        let policy = RetryPolicy::default();
+        assert!(policy.delay(0, &timeout()).is_some());
+        assert!(policy.delay(1, &timeout()).is_some());
+        assert_eq!(policy.delay(2, &timeout()), None);
+
+        let once = RetryPolicy { attempts: 1, ..policy };
+        assert_eq!(once.delay(0, &timeout()), None);
+    }
+
+    #[test]
+    fn does_not_retry_permanent_errors() {
+        let policy = RetryPolicy::default();
+        let auth = ProviderError::AuthFailed {
+            message: "bad key".to_string(),
+        };
+        let bad_request = ProviderError::Http {
+            status: 400,
+            message: "bad request".to_string(),
+            retry_after: None,
+        };
+        assert_eq!(policy.delay(0, &auth), None);
+        assert_eq!(policy.delay(0, &bad_request), None);
+        assert_eq!(policy.delay(0, &ProviderError::config("no model")), None);
+    }
+
+    #[test]
+    fn honours_retry_after_up_to_max_delay() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(0, &rate_limited(Some(5))), Some(Duration::from_secs(5)));
+        assert_eq!(policy.delay(0, &rate_limited(Some(20))), Some(policy.max_delay));
+        assert_eq!(policy.delay(0, &rate_limited(Some(21))), None);
+    }
+
+    #[test]
+    fn backoff_doubles_within_bounds() {
+        let policy = RetryPolicy {
+            attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        for (retry, full) in [(0, 100), (1, 200), (2, 400), (3, 800), (4, 1000), (8, 1000)] {
+            let full = Duration::from_millis(full);
+            for _ in 0..20 {
+                let wait = policy.delay(retry, &rate_limited(None)).unwrap();
+                assert!(wait >= full / 2 && wait <= full, "retry {}: {:?}", retry, wait);
+            }
+        }
+    }
+
+    async fn complete_after(first: MockResponse) -> (MockServer, Result<Completion, ProviderError>) {
+        let server = MockServer::start(vec![
+            first,
+            MockResponse::json(200, json!({ "content": [{ "type": "text", "text": "Hello" }] })),
+        ])
+        .await;
+        let inner = AnthropicProvider::new(reqwest::Client::new(), Some("key"), None, Some(server.url.clone()), None);
+        let result = Retrying::new(inner, fast_policy())
+            .completion(&CompletionRequest::user("Hallo"))
+            .await;
+        (server, result)
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limit() {
+        let first = MockResponse::json(429, json!({ "error": { "message": "slow down" } })).header("Retry-After", "0");
+        let (server, result) = complete_after(first).await;
+        assert_eq!(result.unwrap().text, "Hello");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_server_error() {
+        let (server, result) = complete_after(MockResponse::json(500, json!({ "error": { "message": "boom" } }))).await;
+        assert_eq!(result.unwrap().text, "Hello");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_auth_failure() {
+        let (server, result) = complete_after(MockResponse::json(401, json!({ "error": { "message": "bad key" } }))).await;
+        assert!(matches!(result, Err(ProviderError::AuthFailed { .. })));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    /// Streams "Hel" and then times out, every time.
+    struct FailsMidStream {
+        calls: AtomicU32,
+        chunk_first: bool,
+    }
+
+    impl Provider for FailsMidStream {
+        fn model(&self) -> &str {
+            "test"
+        }
+
+        async fn completion(&self, _request: &CompletionRequest) -> Result<Completion, ProviderError> {
+            unimplemented!()
+        }
+
+        async fn completion_stream<F>(&self, _request: &CompletionRequest, mut on_chunk: F) -> Result<Completion, ProviderError>
+        where
+            F: FnMut(&str) -> bool + Send,
+        {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.chunk_first {
+                on_chunk("Hel");
+            }
+            Err(timeout())
+        }
+
+        async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+            Ok(Vec::new())
+        }
+    }
+
+    async fn stream_calls(chunk_first: bool) -> (u32, Vec<String>) {
+        let provider = Retrying::new(
+            FailsMidStream {
+                calls: AtomicU32::new(0),
+                chunk_first,
+            },
+            fast_policy(),
+        );
+        let mut chunks = Vec::new();
+        let result = provider
+            .completion_stream(&CompletionRequest::user("Hallo"), |delta| {
+                chunks.push(delta.to_string());
+                true
+            })
+            .await;
+        assert!(matches!(result, Err(ProviderError::Timeout { .. })));
+        (provider.inner.calls.load(Ordering::SeqCst), chunks)
+    }
+
+    #[tokio::test]
+    async fn stream_is_not_retried_after_a_chunk() {
+        assert_eq!(stream_calls(true).await, (1, vec!["Hel".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn stream_is_retried_before_any_chunk() {
+        let (calls, chunks) = stream_calls(false).await;
+        assert_eq!(calls, fast_policy().attempts);
+        assert!(chunks.is_empty());
+    }
+}
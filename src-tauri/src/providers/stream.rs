@@ -0,0 +1,61 @@
+use crate::providers::error::ProviderError;
+use reqwest::Response;
+
+/// Reads a streaming HTTP body line by line, calling `on_line` for every
+/// complete line. Returning `Ok(false)` from `on_line` stops reading early,
+/// which drops the connection.
+pub(crate) async fn for_each_line<F>(provider: &str, mut res: Response, mut on_line: F) -> Result<(), ProviderError>
+where
+    F: FnMut(&str) -> Result<bool, ProviderError>,
+{
+    let mut buf: Vec<u8> = Vec::new();
+
+    loop {
+        let bytes = match res.chunk().await {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => break,
+            Err(err) if err.is_timeout() => {
+                return Err(ProviderError::Timeout {
+                    message: format!("{} stream timed out — {}", provider, err),
+                })
+            }
+            Err(err) => {
+                return Err(ProviderError::Other {
+                    message: format!("{} stream interrupted — {}", provider, err),
+                })
+            }
+        };
+        buf.extend_from_slice(&bytes);
+        // Only split on complete lines; a multi-byte character may straddle chunks
+        while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                continue;
+            }
+            if !on_line(line)? {
+                return Ok(());
+            }
+        }
+    }
+
+    // Flush a trailing line that was not newline-terminated
+    let rest = String::from_utf8_lossy(&buf);
+    let rest = rest.trim();
+    if !rest.is_empty() {
+        on_line(rest)?;
+    }
+    Ok(())
+}
+
+/// Extracts the payload of a server-sent event `data:` line. Returns `None`
+/// for comments, other SSE fields and the OpenAI `[DONE]` sentinel.
+pub(crate) fn sse_data(line: &str) -> Option<&str> {
+    let data = line.strip_prefix("data:")?.trim_start();
+    if data == "[DONE]" {
+        None
+    } else {
+        Some(data)
+    }
+}
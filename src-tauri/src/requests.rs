@@ -0,0 +1,113 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use crate::providers::error::ProviderError;
+use tauri::{Emitter, Manager};
+use tokio::sync::oneshot;
+
+struct InFlight {
+    window: String,
+    cancel: oneshot::Sender<()>,
+}
+
+/// Tracks every running translate/correct/refine call so it can be aborted by
+/// ID, or all at once when the window that started it goes away.
+#[derive(Default)]
+pub struct InFlightRequests(Mutex<HashMap<String, InFlight>>);
+
+impl InFlightRequests {
+    /// Returns None if a request with this ID is already running, so a reused
+    /// ID cannot take over another request's cancellation.
+    fn register(&self, id: &str, window: &str) -> Option<oneshot::Receiver<()>> {
+        let (tx, rx) = oneshot::channel();
+        if let Ok(mut map) = self.0.lock() {
+            match map.entry(id.to_string()) {
+                Entry::Occupied(_) => return None,
+                Entry::Vacant(slot) => {
+                    slot.insert(InFlight {
+                        window: window.to_string(),
+                        cancel: tx,
+                    });
+                }
+            }
+        }
+        Some(rx)
+    }
+
+    fn finish(&self, id: &str) {
+        if let Ok(mut map) = self.0.lock() {
+            map.remove(id);
+        }
+    }
+
+    /// Cancels a single request. Returns false if it had already finished.
+    pub fn cancel(&self, id: &str) -> bool {
+        let entry = self.0.lock().ok().and_then(|mut map| map.remove(id));
+        match entry {
+            Some(entry) => entry.cancel.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Cancels every request started from the given window.
+    pub fn cancel_window(&self, label: &str) {
+        let entries: Vec<InFlight> = match self.0.lock() {
+            Ok(mut map) => {
+                let ids: Vec<String> = map
+                    .iter()
+                    .filter(|(_, e)| e.window == label)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                ids.iter().filter_map(|id| map.remove(id)).collect()
+            }
+            Err(_) => return,
+        };
+        for entry in entries {
+            let _ = entry.cancel.send(());
+        }
+    }
+}
+
+/// Returns a process-unique request ID for callers that did not supply one.
+pub fn new_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    format!("{}-{}", now, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Runs `fut` as request `id` on behalf of `window`. The future is dropped —
+/// aborting any HTTP request it holds — as soon as the request is cancelled.
+/// Emits `request-started` to the window first so the frontend learns the ID.
+/// Fails without running `fut` if `id` is already in flight.
+pub async fn run_cancellable<T, Fut>(
+    window: &tauri::Window,
+    id: &str,
+    fut: Fut,
+) -> Result<T, ProviderError>
+where
+    Fut: Future<Output = Result<T, ProviderError>>,
+{
+    let requests = window.state::<InFlightRequests>();
+    let cancelled = requests.register(id, window.label()).ok_or_else(|| {
+        ProviderError::config(format!("Request ID '{}' is already in flight", id))
+    })?;
+    let _ = window.emit_to(window.label(), "request-started", id);
+
+    let res = tokio::select! {
+        res = fut => res,
+        Ok(()) = cancelled => Err(ProviderError::Cancelled),
+    };
+
+    requests.finish(id);
+    res
+}
+
+#[tauri::command]
+pub async fn cancel_request(app_handle: tauri::AppHandle, request_id: String) -> bool {
+    app_handle.state::<InFlightRequests>().cancel(&request_id)
+}
@@ -0,0 +1,148 @@
+use crate::history::with_db;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri_plugin_store::StoreExt;
+
+/// How often the background task prunes history after the startup pass.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How much history to keep and what never to record, saved as
+/// `HISTORY_MAX_AGE_DAYS`, `HISTORY_MAX_ENTRIES`, `HISTORY_MAX_SIZE_MB` and
+/// `HISTORY_EXCLUDE_SENSITIVE`. Unset limits do not apply.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryRetention {
+    pub max_age_days: Option<u32>,
+    pub max_entries: Option<u64>,
+    /// Cap on the stored text (input plus output), in megabytes
+    pub max_size_mb: Option<u64>,
+    /// Skip recording entries that contain emails, card numbers or API keys
+    #[serde(default)]
+    pub exclude_sensitive: bool,
+}
+
+impl HistoryRetention {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let store = match app.store("store.bin") {
+            Ok(store) => store,
+            Err(_) => return Self::default(),
+        };
+        // Zero is treated like unset: "keep nothing" is what clearing is for
+        let limit = |key: &str| store.get(key).and_then(|v| v.as_u64()).filter(|n| *n > 0);
+        Self {
+            max_age_days: limit("HISTORY_MAX_AGE_DAYS").map(|n| n.min(u32::MAX as u64) as u32),
+            max_entries: limit("HISTORY_MAX_ENTRIES"),
+            max_size_mb: limit("HISTORY_MAX_SIZE_MB"),
+            exclude_sensitive: store
+                .get("HISTORY_EXCLUDE_SENSITIVE")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        }
+    }
+
+    fn has_limits(&self) -> bool {
+        self.max_age_days.is_some() || self.max_entries.is_some() || self.max_size_mb.is_some()
+    }
+}
+
+/// Deletes entries beyond the retention limits, oldest first, in one
+/// transaction. Returns how many were removed.
+pub fn prune(app: &tauri::AppHandle) -> Result<usize, String> {
+    let policy = HistoryRetention::load(app);
+    if !policy.has_limits() {
+        return Ok(0);
+    }
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let removed = with_db(app, |conn| {
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        if let Some(days) = policy.max_age_days {
+            let cutoff = now_ms.saturating_sub(days as u64 * 86_400 * 1_000);
+            removed += tx.execute("DELETE FROM history WHERE timestamp < ?1", [cutoff as i64])?;
+        }
+        if let Some(max) = policy.max_entries {
+            removed += tx.execute(
+                "DELETE FROM history WHERE id NOT IN (
+                     SELECT id FROM history ORDER BY timestamp DESC, id DESC LIMIT ?1
+                 )",
+                [max.min(i64::MAX as u64) as i64],
+            )?;
+        }
+        if let Some(mb) = policy.max_size_mb {
+            // Keep the newest entries whose running text size fits the cap
+            removed += tx.execute(
+                "DELETE FROM history WHERE id IN (
+                     SELECT id FROM (
+                         SELECT id, SUM(length(CAST(input_text AS BLOB))
+                                        + length(CAST(coalesce(output_text, '') AS BLOB)))
+                                    OVER (ORDER BY timestamp DESC, id DESC) AS running
+                         FROM history
+                     ) WHERE running > ?1
+                 )",
+                [(mb * 1024 * 1024).min(i64::MAX as u64) as i64],
+            )?;
+        }
+        tx.commit()?;
+
+        // Hand the freed pages back to the file system
+        if removed > 0 {
+            conn.execute_batch("INSERT INTO history_fts(history_fts) VALUES ('optimize'); VACUUM;")?;
+        }
+        Ok(removed)
+    })?;
+    // Cached answers cannot be matched to history rows, so they go too
+    // rather than outlive the limits
+    if removed > 0 {
+        crate::cache::clear(app)?;
+    }
+    Ok(removed)
+}
+
+/// Prunes once now and then once a day for the life of the app.
+pub fn spawn_pruning(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            match tauri::async_runtime::spawn_blocking(move || prune(&handle)).await {
+                Ok(Err(e)) => eprintln!("Failed to prune history: {}", e),
+                Err(e) => eprintln!("History pruning task failed: {}", e),
+                Ok(Ok(_)) => {}
+            }
+            tokio::time::sleep(PRUNE_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_history_retention(app_handle: tauri::AppHandle) -> HistoryRetention {
+    HistoryRetention::load(&app_handle)
+}
+
+/// Saves the retention settings and applies the new limits right away.
+/// Returns how many entries were pruned.
+#[tauri::command]
+pub async fn set_history_retention(
+    app_handle: tauri::AppHandle,
+    retention: HistoryRetention,
+) -> Result<usize, String> {
+    let store = app_handle.store("store.bin").map_err(|e| e.to_string())?;
+    for (key, value) in [
+        ("HISTORY_MAX_AGE_DAYS", retention.max_age_days.map(u64::from)),
+        ("HISTORY_MAX_ENTRIES", retention.max_entries),
+        ("HISTORY_MAX_SIZE_MB", retention.max_size_mb),
+    ] {
+        match value.filter(|n| *n > 0) {
+            Some(n) => store.set(key, n),
+            None => {
+                store.delete(key);
+            }
+        }
+    }
+    store.set("HISTORY_EXCLUDE_SENSITIVE", retention.exclude_sensitive);
+    store.save().map_err(|e| e.to_string())?;
+
+    prune(&app_handle)
+}
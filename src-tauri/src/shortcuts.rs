@@ -13,9 +13,6 @@ const DEBOUNCE_DURATION: Duration = Duration::from_millis(300);
 
 /// Common shortcut handler that processes the shortcut logic for both platforms
 async fn handle_shortcut_common(app_handle: tauri::AppHandle) {
-    // Get the window type preference
-    let window_type = get_shortcut_window_type(app_handle.clone()).await.unwrap_or("main".to_string());
-    
     // Get selected text with platform-specific handling
     let selected_text_result = {
         #[cfg(target_os = "windows")]
@@ -27,41 +24,52 @@ async fn handle_shortcut_common(app_handle: tauri::AppHandle) {
             get_selected_text(&app_handle).await
         }
     };
-    
+
     if let Ok(selected_text) = selected_text_result {
-        let text = selected_text.trim();
-        if !text.is_empty() {
-            let window_result = match window_type.as_str() {
-                "popup" => create_or_focus_compact_window(&app_handle).await,
-                "main" => {
-                    if let Some(win) = app_handle.get_webview_window("main") {
-                        Ok(win)
-                    } else {
-                        Err("Main window not found".to_string())
-                    }
-                },  
-                _ => Err("Invalid window type".to_string()),
-            };
-            let event_name = match window_type.as_str() {
-                "popup" => "shortcut-popup-translate",
-                "main" => "shortcut-main-translate",
-                _ => "shortcut-main-translate",
-            };
+        show_selected_text(&app_handle, &selected_text).await;
+    }
+}
 
-            if let Ok(window) = window_result {
-                // Store pending text so the popup can read it on mount
-                // (handles the race condition where emit fires before JS loads)
-                if window_type == "popup" {
-                    if let Ok(store) = app_handle.store("store.bin") {
-                        store.set("POPUP_PENDING_TEXT", text);
-                    }
-                }
+/// Opens the window chosen by `SHORTCUT_WINDOW_TYPE` and hands it the text
+/// to translate. Shared by the shortcut and the mouse service's selections.
+pub async fn show_selected_text(app_handle: &tauri::AppHandle, selected_text: &str) {
+    let text = selected_text.trim();
+    if text.is_empty() {
+        return;
+    }
 
-                let _ = window.show();
-                let _ = window.set_focus();
-                let _ = window.emit(event_name, format!("text:{}", text));
+    // Get the window type preference
+    let window_type = get_shortcut_window_type(app_handle.clone()).await.unwrap_or("main".to_string());
+
+    let window_result = match window_type.as_str() {
+        "popup" => create_or_focus_compact_window(app_handle).await,
+        "main" => {
+            if let Some(win) = app_handle.get_webview_window("main") {
+                Ok(win)
+            } else {
+                Err("Main window not found".to_string())
+            }
+        },  
+        _ => Err("Invalid window type".to_string()),
+    };
+    let event_name = match window_type.as_str() {
+        "popup" => "shortcut-popup-translate",
+        "main" => "shortcut-main-translate",
+        _ => "shortcut-main-translate",
+    };
+
+    if let Ok(window) = window_result {
+        // Store pending text so the popup can read it on mount
+        // (handles the race condition where emit fires before JS loads)
+        if window_type == "popup" {
+            if let Ok(store) = app_handle.store("store.bin") {
+                store.set("POPUP_PENDING_TEXT", text);
             }
         }
+
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.emit(event_name, format!("text:{}", text));
     }
 }
 
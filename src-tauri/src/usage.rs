@@ -0,0 +1,225 @@
+use crate::providers::base::Usage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+/// Optional prices used to estimate spend, saved as
+/// `PRICE_TABLE: { model: { input, output } }`.
+const PRICE_TABLE_KEY: &str = "PRICE_TABLE";
+
+/// Serialises read-modify-write of the ledger file between concurrent requests.
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Token totals for one provider and model on one UTC day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// `YYYY-MM-DD`, UTC
+    pub date: String,
+    /// Provider or profile name the request went through
+    pub provider: String,
+    pub model: String,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct UsageLedger {
+    records: Vec<UsageRecord>,
+}
+
+/// US dollars per million tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+fn ledger_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("usage.json"))
+}
+
+fn load_ledger(app: &tauri::AppHandle) -> Result<UsageLedger, String> {
+    let path = ledger_path(app)?;
+    if !path.exists() {
+        return Ok(UsageLedger::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&content) {
+        Ok(ledger) => Ok(ledger),
+        Err(e) => {
+            // Keep the unreadable file for inspection and start a new ledger,
+            // rather than failing every request from now on
+            let aside = path.with_extension("json.corrupt");
+            eprintln!("Usage ledger is unreadable ({}); moving it to {}", e, aside.display());
+            fs::rename(&path, &aside).map_err(|e| e.to_string())?;
+            Ok(UsageLedger::default())
+        }
+    }
+}
+
+/// Writes the ledger to a temporary file and renames it into place, so a
+/// crash mid-write leaves the previous ledger intact.
+fn save_ledger(app: &tauri::AppHandle, ledger: &UsageLedger) -> Result<(), String> {
+    let path = ledger_path(app)?;
+    let tmp = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(ledger).map_err(|e| e.to_string())?;
+    fs::write(&tmp, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn date_string(secs: u64) -> String {
+    let (year, mon, day) = crate::language_analysis::utc_date(secs);
+    format!("{:04}-{:02}-{:02}", year, mon, day)
+}
+
+fn load_prices(app: &tauri::AppHandle) -> HashMap<String, ModelPrice> {
+    app.store("store.bin")
+        .ok()
+        .and_then(|s| s.get(PRICE_TABLE_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Adds one request's usage to today's bucket. Failures are logged, never
+/// surfaced: accounting must not break a translation.
+pub fn record(app: &tauri::AppHandle, provider: &str, model: &str, usage: Usage) {
+    let _guard = LEDGER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let date = date_string(now_secs());
+
+    let result = load_ledger(app).and_then(|mut ledger| {
+        match ledger
+            .records
+            .iter_mut()
+            .find(|r| r.date == date && r.provider == provider && r.model == model)
+        {
+            Some(record) => {
+                record.requests += 1;
+                record.prompt_tokens += usage.prompt_tokens;
+                record.completion_tokens += usage.completion_tokens;
+            }
+            None => ledger.records.push(UsageRecord {
+                date,
+                provider: provider.to_string(),
+                model: model.to_string(),
+                requests: 1,
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+            }),
+        }
+        save_ledger(app, &ledger)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record token usage: {}", e);
+    }
+}
+
+// ── Commands ───────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct UsageSummaryRow {
+    #[serde(flatten)]
+    pub record: UsageRecord,
+    /// `None` when the model has no entry in the price table
+    pub estimated_cost: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct UsageSummary {
+    /// Newest day first
+    pub rows: Vec<UsageSummaryRow>,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Sum over priced rows only; `None` if no row could be priced
+    pub estimated_cost: Option<f64>,
+    /// Models that appear in `rows` but have no price
+    pub unpriced_models: Vec<String>,
+}
+
+/// Summarises token usage for the last `days` days including today, or for
+/// all recorded days when `days` is not given.
+#[tauri::command]
+pub async fn get_usage_summary(app_handle: tauri::AppHandle, days: Option<u32>) -> Result<UsageSummary, String> {
+    let ledger = {
+        let _guard = LEDGER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        load_ledger(&app_handle)?
+    };
+    let prices = load_prices(&app_handle);
+    let since = days.map(|d| date_string(now_secs().saturating_sub(d.saturating_sub(1) as u64 * 86400)));
+
+    let mut rows: Vec<UsageSummaryRow> = ledger
+        .records
+        .into_iter()
+        // YYYY-MM-DD strings order the same as the dates
+        .filter(|r| match &since {
+            Some(since) => r.date >= *since,
+            None => true,
+        })
+        .map(|record| {
+            let estimated_cost = prices.get(&record.model).map(|p| {
+                (record.prompt_tokens as f64 * p.input + record.completion_tokens as f64 * p.output) / 1_000_000.0
+            });
+            UsageSummaryRow { record, estimated_cost }
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.record
+            .date
+            .cmp(&a.record.date)
+            .then_with(|| a.record.provider.cmp(&b.record.provider))
+            .then_with(|| a.record.model.cmp(&b.record.model))
+    });
+
+    let priced: Vec<f64> = rows.iter().filter_map(|r| r.estimated_cost).collect();
+    let unpriced_models: BTreeSet<String> = rows
+        .iter()
+        .filter(|r| r.estimated_cost.is_none())
+        .map(|r| r.record.model.clone())
+        .collect();
+
+    Ok(UsageSummary {
+        requests: rows.iter().map(|r| r.record.requests).sum(),
+        prompt_tokens: rows.iter().map(|r| r.record.prompt_tokens).sum(),
+        completion_tokens: rows.iter().map(|r| r.record.completion_tokens).sum(),
+        estimated_cost: (!priced.is_empty()).then(|| priced.iter().sum()),
+        unpriced_models: unpriced_models.into_iter().collect(),
+        rows,
+    })
+}
+
+#[tauri::command]
+pub async fn get_price_table(app_handle: tauri::AppHandle) -> Result<HashMap<String, ModelPrice>, String> {
+    Ok(load_prices(&app_handle))
+}
+
+/// Replaces the price table; an empty table removes it.
+#[tauri::command]
+pub async fn set_price_table(
+    app_handle: tauri::AppHandle,
+    prices: HashMap<String, ModelPrice>,
+) -> Result<(), String> {
+    if let Some((model, _)) = prices.iter().find(|(_, p)| p.input < 0.0 || p.output < 0.0) {
+        return Err(format!("Price for \"{}\" cannot be negative", model));
+    }
+    let store = app_handle.store("store.bin").map_err(|e| e.to_string())?;
+    if prices.is_empty() {
+        store.delete(PRICE_TABLE_KEY);
+    } else {
+        store.set(PRICE_TABLE_KEY, serde_json::json!(prices));
+    }
+    store.save().map_err(|e| e.to_string())
+}
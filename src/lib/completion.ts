@@ -0,0 +1,64 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+import type {
+  CompletionChunk,
+  CompletionResult,
+  ProviderError,
+} from "@/types/translate";
+
+let counter = 0;
+
+function newRequestId(): string {
+  counter += 1;
+  return `ui-${Date.now()}-${counter}`;
+}
+
+/**
+ * Invokes translate/smart_translate/correct/refine and calls `onText` with the text received
+ * so far each time a `completion-chunk` for this request arrives, so the view
+ * can render while the model is still generating.
+ */
+export async function runCompletion(
+  command: string,
+  args: Record<string, unknown>,
+  onText: (text: string) => void,
+): Promise<CompletionResult> {
+  const requestId = newRequestId();
+  const unlisten = await listen<CompletionChunk>("completion-chunk", (event) => {
+    if (event.payload.request_id === requestId) onText(event.payload.text);
+  });
+  try {
+    return await invoke<CompletionResult>(command, { ...args, requestId });
+  } finally {
+    unlisten();
+  }
+}
+
+const HINTS: Partial<Record<ProviderError["kind"], string>> = {
+  auth_failed: "Check the API key in Settings.",
+  rate_limited: "The provider is rate limiting requests; wait a moment.",
+  connection_refused: "Is the provider running, and is its URL in Settings right?",
+  model_not_found: "Pick another model in Settings.",
+  timeout: "The provider took too long to answer; try again.",
+  config: "Finish setting up the provider in Settings.",
+};
+
+function isProviderError(e: unknown): e is ProviderError {
+  return typeof e === "object" && e !== null && "kind" in e;
+}
+
+/** Turns whatever a translate/correct/refine call rejected with into text to
+ * show in place of the result. */
+export function describeError(e: unknown): string {
+  if (!isProviderError(e)) return `Error: ${String(e)}`;
+  if (e.kind === "cancelled") return "Cancelled.";
+
+  const parts = [`Error: ${e.message}`];
+  const hint = HINTS[e.kind];
+  if (hint) parts.push(hint);
+  if ((e.kind === "rate_limited" || e.kind === "http") && e.retry_after) {
+    parts.push(`Retry in ${e.retry_after}s.`);
+  }
+  return parts.join(" ");
+}
@@ -0,0 +1,69 @@
+export interface HistoryEntry {
+  id: number;
+  timestamp: number;
+  mode: string;
+  input_text: string;
+  source_lang: string;
+  target_lang: string;
+  source_confidence?: number;
+  output_text: string | null;
+  provider: string | null;
+  model: string | null;
+  latency_ms: number | null;
+  prompt_variant: string | null;
+}
+
+/** Narrows `get_history_count`, `export_history_json`, `clear_history` and
+ * `search_history`; omitted fields do not filter. */
+export interface HistoryFilter {
+  mode?: string;
+  source_lang?: string;
+  target_lang?: string;
+  /** Inclusive, Unix milliseconds */
+  since_ms?: number;
+  /** Exclusive, Unix milliseconds */
+  until_ms?: number;
+}
+
+/** Entries for one mode and language pair. */
+export interface HistoryCountGroup {
+  mode: string;
+  source_lang: string;
+  target_lang: string;
+  count: number;
+}
+
+/** What `get_history_count` resolves to; `groups` is largest first. */
+export interface HistoryCount {
+  total: number;
+  groups: HistoryCountGroup[];
+}
+
+/** Arguments of `search_history`. */
+export interface HistoryQuery extends HistoryFilter {
+  query?: string;
+  offset?: number;
+  limit?: number;
+}
+
+/** Snippets wrap each match between these two characters. */
+export const HIGHLIGHT_START = "\u0002";
+export const HIGHLIGHT_END = "\u0003";
+
+export interface HistoryHit extends HistoryEntry {
+  input_snippet: string | null;
+  output_snippet: string | null;
+}
+
+export interface HistorySearchResult {
+  hits: HistoryHit[];
+  total: number;
+}
+
+/** `get_history_retention` / `set_history_retention`; unset limits do not apply. */
+export interface HistoryRetention {
+  max_age_days: number | null;
+  max_entries: number | null;
+  max_size_mb: number | null;
+  exclude_sensitive: boolean;
+}
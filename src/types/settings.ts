@@ -21,8 +21,39 @@ export const providerMap: Record<string, Provider> = {
     label: "Groq",
     models: ["llama-3.1-8b-instant", "gemma2-9b-it"],
   },
+  anthropic: {
+    name: "anthropic",
+    label: "Anthropic",
+    models: ["claude-haiku-4-5", "claude-sonnet-4-5"],
+  },
+  "openai-compatible": {
+    name: "openai-compatible",
+    label: "OpenAI-compatible",
+  },
 };
 
+/** An entry returned by the `list_models` command. */
+export interface ModelInfo {
+  id: string;
+  context_length: number | null;
+  supports_thinking: boolean | null;
+}
+
+/** What `test_provider` resolves to when the probe succeeds. */
+export interface ProviderTestResult {
+  latency_ms: number;
+  model: string;
+  reply: string;
+}
+
+/** Per-mode sampling settings; unset fields use the provider default. */
+export interface GenerationParams {
+  temperature?: number | null;
+  top_p?: number | null;
+  max_tokens?: number | null;
+  seed?: number | null;
+}
+
 export interface PromptSettings {
   translate?: string;
   correct?: string;
@@ -16,6 +16,53 @@ export interface ResultTexts {
 
 export type Mode = "Translate" | "Correct" | "Refine";
 
+/** What translate/correct/refine resolve to; `provider` names whichever
+ * provider or profile answered, including a fallback. */
+export interface CompletionResult {
+  text: string;
+  provider: string;
+  model: string;
+  usage: { prompt_tokens: number; completion_tokens: number } | null;
+  cached: boolean;
+  /** Set when the source language was detected rather than given */
+  detected_language: DetectedLanguage | null;
+  /** The language `smart_translate` chose to translate into */
+  target_lang: string | null;
+}
+
+/** Payload of the `completion-chunk` event; `text` is everything received so
+ * far for `request_id`. */
+export interface CompletionChunk {
+  request_id: string;
+  mode: string;
+  provider: string;
+  delta: string;
+  text: string;
+}
+
+/** What translate/correct/refine reject with. */
+export type ProviderError =
+  | {
+      kind:
+        | "connection_refused"
+        | "auth_failed"
+        | "model_not_found"
+        | "bad_response"
+        | "timeout"
+        | "config"
+        | "other";
+      message: string;
+    }
+  | { kind: "rate_limited"; message: string; retry_after?: number }
+  | { kind: "http"; status: number; message: string; retry_after?: number }
+  | { kind: "cancelled" };
+
+export interface DetectedLanguage {
+  code: string;
+  name: string;
+  confidence: number;
+}
+
 export interface TranslateContextType {
   languageConfig: LanguageConfig;
   inputText?: string;
@@ -22,6 +22,7 @@ import {
   SelectTrigger,
   SelectValue,
 } from "@/components/ui/select";
+import { describeError, runCompletion } from "@/lib/completion";
 import type { Mode } from "@/types/translate";
 
 // ── Tauri invoke ──────────────────────────────────────────────────────────────
@@ -34,16 +35,22 @@ async function runMode(
   sourceLang?: string,
   targetLang?: string,
   customPrompt?: string,
+  onText?: (partial: string) => void,
 ): Promise<string> {
   const fn = mode.toLowerCase();
-  return invoke<string>(fn, {
-    provider: provider || null,
-    model: model || null,
-    text,
-    sourceLang: sourceLang ?? "English",
-    targetLang: targetLang ?? "Tiếng Việt",
-    prompt: customPrompt || null,
-  });
+  const result = await runCompletion(
+    fn,
+    {
+      provider: provider || null,
+      model: model || null,
+      text,
+      sourceLang: sourceLang ?? "English",
+      targetLang: targetLang ?? "Tiếng Việt",
+      prompt: customPrompt || null,
+    },
+    onText ?? (() => {}),
+  );
+  return result.text;
 }
 
 // ── Mode Tabs ─────────────────────────────────────────────────────────────────
@@ -270,6 +277,8 @@ export default function MainView() {
     ctx.changeResult({});
     const modeKey =
       ctx.currentMode.toLowerCase() as keyof typeof settings.prompts;
+    const show = (answer: string) =>
+      ctx.changeResult({ [ctx.currentMode.toLowerCase()]: answer });
     runMode(
       ctx.inputText ?? "",
       ctx.currentMode,
@@ -278,11 +287,13 @@ export default function MainView() {
       ctx.languageConfig.sourceLang.label,
       ctx.languageConfig.targetLang.label,
       settings.prompts?.[modeKey],
+      show,
     )
-      .then((answer) => {
-        ctx.changeResult({ [ctx.currentMode.toLowerCase()]: answer });
+      .then(show)
+      .catch((e) => {
+        console.error(e);
+        show(describeError(e));
       })
-      .catch(console.error)
       .finally(() => {
         setLoading(false);
         ctx.setTranslating(false);
@@ -340,6 +351,8 @@ export default function MainView() {
         ctx.setTranslating(true);
         ctx.changeResult({});
         const modeKey = mode.toLowerCase() as keyof typeof settings.prompts;
+        const show = (answer: string) =>
+          ctx.changeResult({ [mode.toLowerCase()]: answer });
         runMode(
           ctx.inputText,
           mode,
@@ -348,9 +361,13 @@ export default function MainView() {
           ctx.languageConfig.sourceLang.label,
           ctx.languageConfig.targetLang.label,
           settings.prompts?.[modeKey],
+          show,
         )
-          .then((answer) => ctx.changeResult({ [mode.toLowerCase()]: answer }))
-          .catch(console.error)
+          .then(show)
+          .catch((e) => {
+            console.error(e);
+            show(describeError(e));
+          })
           .finally(() => {
             setLoading(false);
             ctx.setTranslating(false);
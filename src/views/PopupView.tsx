@@ -1,5 +1,4 @@
 import { useContext, useEffect, useRef, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { WebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { load } from "@tauri-apps/plugin-store";
@@ -9,25 +8,36 @@ import { Copy, Check, ExternalLink, Languages, Sparkles } from "lucide-react";
 import { TranslateContext } from "@/providers/translate";
 import { SettingContext } from "@/providers/settings";
 import { SkeletonText } from "@/components/ui/skeleton";
+import { describeError, runCompletion } from "@/lib/completion";
 
 type Tab = "translate" | "correct";
 
+/**
+ * With a preferred language set, uses smart translation: text already in the
+ * preferred language goes to the secondary one instead of being echoed back.
+ */
 async function translateText(options: {
   text: string;
   provider?: string;
   model?: string;
   prompt?: string;
   preferredLang?: string;
+  onText: (partial: string) => void;
 }): Promise<string> {
-  const lang = options.preferredLang || "Tiếng Việt";
-  return invoke<string>("translate", {
+  const common = {
     provider: options.provider || null,
     model: options.model || null,
     text: options.text,
-    sourceLang: "auto",
-    targetLang: lang,
     prompt: options.prompt || null,
-  });
+  };
+  const result = options.preferredLang
+    ? await runCompletion("smart_translate", common, options.onText)
+    : await runCompletion(
+        "translate",
+        { ...common, sourceLang: "auto", targetLang: "Tiếng Việt" },
+        options.onText,
+      );
+  return result.text;
 }
 
 async function correctText(options: {
@@ -36,15 +46,21 @@ async function correctText(options: {
   model?: string;
   prompt?: string;
   preferredLang?: string;
+  onText: (partial: string) => void;
 }): Promise<string> {
-  return invoke<string>("correct", {
-    provider: options.provider || null,
-    model: options.model || null,
-    text: options.text,
-    sourceLang: "auto",
-    targetLang: "the original language of the text",
-    prompt: options.prompt || null,
-  });
+  const result = await runCompletion(
+    "correct",
+    {
+      provider: options.provider || null,
+      model: options.model || null,
+      text: options.text,
+      sourceLang: "auto",
+      targetLang: "the original language of the text",
+      prompt: options.prompt || null,
+    },
+    options.onText,
+  );
+  return result.text;
 }
 
 async function openMainWindow() {
@@ -307,6 +323,7 @@ export default function PopupView() {
     const { provider, model, prompts, preferredLang } = settingsRef.current;
     ctxRef.current.setTranslating(true);
     ctxRef.current.changeResult({ translate: "", correct: "" });
+    let finished: string | undefined;
 
     translateText({
       text,
@@ -314,8 +331,11 @@ export default function PopupView() {
       model,
       prompt: prompts?.translate,
       preferredLang,
+      onText: (partial) =>
+        ctxRef.current.changeResult({ translate: partial, correct: "" }),
     })
       .then((translation) => {
+        finished = translation;
         ctxRef.current.changeResult({ translate: translation, correct: "" });
         return correctText({
           text,
@@ -323,6 +343,11 @@ export default function PopupView() {
           model,
           prompt: prompts?.correct,
           preferredLang,
+          onText: (partial) =>
+            ctxRef.current.changeResult({
+              translate: translation,
+              correct: partial,
+            }),
         }).then((correction) => {
           ctxRef.current.changeResult({
             translate: translation,
@@ -332,9 +357,11 @@ export default function PopupView() {
       })
       .catch((e) => {
         console.error(e);
+        // Keep a finished translation when only the correction failed
+        const message = describeError(e);
         ctxRef.current.changeResult({
-          translate: "Translation failed",
-          correct: "Correction failed",
+          translate: finished ?? message,
+          correct: message,
         });
       })
       .finally(() => {
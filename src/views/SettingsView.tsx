@@ -11,6 +11,7 @@ import { listen } from "@tauri-apps/api/event";
 import { SettingContext } from "@/providers/settings";
 import { providerMap } from "@/types/settings";
 import type { ShortcutWindowType, TextSizeType } from "@/types/settings";
+import type { HistoryCount, HistoryCountGroup } from "@/types/history";
 import { Input } from "@/components/ui/input";
 import { Switch } from "@/components/ui/switch";
 import { Button } from "@/components/ui/button";
@@ -117,6 +118,7 @@ interface AnalysisState {
 function AnalysisTab() {
   const [enabled, setEnabled] = useState(false);
   const [count, setCount] = useState(0);
+  const [groups, setGroups] = useState<HistoryCountGroup[]>([]);
   const [daysBack, setDaysBack] = useState("30");
   const [reports, setReports] = useState<ReportInfo[]>([]);
   const [analysis, setAnalysis] = useState<AnalysisState>({ phase: "idle", message: "", percent: 0 });
@@ -169,7 +171,12 @@ function AnalysisTab() {
 
   useEffect(() => {
     invoke<boolean>("get_history_enabled").then(setEnabled).catch(() => {});
-    invoke<number>("get_history_count").then(setCount).catch(() => {});
+    invoke<HistoryCount>("get_history_count")
+      .then((c) => {
+        setCount(c.total);
+        setGroups(c.groups);
+      })
+      .catch(() => {});
     refreshReports();
   }, []);
 
@@ -180,8 +187,8 @@ function AnalysisTab() {
 
   async function handleExport() {
     try {
-      await invoke("export_history_json");
-      flash("ok", "Exported — opened in default app.");
+      const path = await invoke<string | null>("export_history_json");
+      if (path) flash("ok", `Exported to ${path}`);
     } catch (e) { flash("err", String(e)); }
   }
 
@@ -189,6 +196,7 @@ function AnalysisTab() {
     if (!window.confirm(`Clear all ${count} history entries? This cannot be undone.`)) return;
     await invoke("clear_history").catch(() => {});
     setCount(0);
+    setGroups([]);
     flash("ok", "History cleared.");
   }
 
@@ -226,7 +234,12 @@ function AnalysisTab() {
         </Row>
         {count > 0 && (
           <div className="flex items-center justify-between gap-2 pt-0.5">
-            <span className="text-[11px] text-[var(--text-tertiary)]">
+            <span
+              className="text-[11px] text-[var(--text-tertiary)]"
+              title={groups
+                .map((g) => `${g.mode} ${g.source_lang} → ${g.target_lang}: ${g.count}`)
+                .join("\n")}
+            >
               {count} {count === 1 ? "entry" : "entries"} collected
             </span>
             <div className="flex gap-2">